@@ -0,0 +1,267 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::path::{Path, PathBuf};
+use AMZL_Staffing::models::{Algorithm, Assignment, GapPolicy};
+use AMZL_Staffing::utils::{Config, SheetSelector};
+
+/// Exit codes so scripts can tell failures apart. Clap uses 2 for usage errors.
+pub const EXIT_USAGE: u8 = 2;
+pub const EXIT_CONFIG: u8 = 3;
+pub const EXIT_INPUT: u8 = 4;
+pub const EXIT_PLAN: u8 = 5;
+pub const EXIT_OUTPUT: u8 = 6;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Evenly distribute aisles between stowers")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// CSV or .xlsx files to plan with the saved config (what dragging files onto the exe does)
+    pub files: Vec<PathBuf>,
+
+    /// Exit straight away instead of waiting for Enter
+    #[arg(long, global = true)]
+    pub no_pause: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Build a stow slot plan
+    Plan(Box<PlanArgs>),
+    /// Print the floor summary only
+    Summary {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Stow hours used for the floor PPH
+        #[arg(long)]
+        hours: Option<f32>,
+    },
+}
+
+/// Where the planned bags come from and how picky to be about bad rows.
+#[derive(Args, Debug, Default)]
+pub struct InputArgs {
+    /// Planned bag exports, as .csv or .xlsx; several files (e.g. waves) are merged
+    #[arg(required = true, value_name = "FILES")]
+    pub files: Vec<PathBuf>,
+
+    /// Keep the bags of each file apart and report PPH per file (wave) for every slot
+    #[arg(long)]
+    pub by_source: bool,
+
+    /// Worksheet to read from an .xlsx file, by name or number (from 1) [default: first]
+    #[arg(long)]
+    pub sheet: Option<SheetSelector>,
+
+    /// Fail on the first file with bad rows instead of skipping them (for CI checks)
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Write skipped rows, with line numbers and reasons, to this CSV
+    #[arg(long, value_name = "PATH")]
+    pub quarantine: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq)]
+pub enum OutputFormat {
+    /// Human readable console output
+    #[default]
+    Text,
+    /// The versioned JSON plan document, and nothing else, on stdout
+    Json,
+}
+
+#[derive(Args, Debug)]
+pub struct PlanArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    /// Where to write the stow slot CSV [default: <FILE>_stow_slots.csv next to the first input]
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Also write printable assignment sheets [default: <FILE>_sheets.html next to the first input]
+    #[arg(long, value_name = "PATH")]
+    pub sheets: Option<Option<PathBuf>>,
+
+    /// Also write the numbered list of changes that produced the plan
+    #[arg(long, value_name = "PATH")]
+    pub change_log: Option<PathBuf>,
+
+    /// How to print the finished plan
+    #[arg(long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+
+    /// Adjust the plan in a terminal editor before it is printed and written
+    #[arg(short, long, conflicts_with = "format")]
+    pub interactive: bool,
+
+    /// Reserve aisles for a named stower, e.g. `Sam=A-7..A-10` (repeatable)
+    #[arg(long = "assign", value_name = "NAME=AISLES")]
+    pub assign: Vec<Assignment>,
+
+    /// CSV of pre-assignments with `Stower` and `Aisles` columns
+    #[arg(long, value_name = "PATH")]
+    pub assignments: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub overrides: ConfigOverrides,
+}
+
+/// Command-line overrides for the planning settings of the saved `Config`. The sort zone
+/// pattern, column aliases and workload weights can only be set in the config file.
+#[derive(Args, Debug, Default)]
+pub struct ConfigOverrides {
+    /// Target PPH per stow slot (TargetPPH)
+    #[arg(long)]
+    pub target_pph: Option<i32>,
+    /// Number of stowers to plan for
+    #[arg(long)]
+    pub target_hc: Option<i32>,
+    /// Total stow hours
+    #[arg(long)]
+    pub hours: Option<f32>,
+    /// TargetPPH, TargetHC, Balanced or Proportional
+    #[arg(long)]
+    pub algorithm: Option<Algorithm>,
+    /// Fewest aisle pairs per stow slot
+    #[arg(long)]
+    pub min_aisles: Option<i32>,
+    /// Most aisle pairs per stow slot
+    #[arg(long)]
+    pub max_aisles: Option<i32>,
+    /// Slots at or below this PPH are floaters
+    #[arg(long)]
+    pub floater_pph: Option<f32>,
+    /// Floater slots to carve out of every cluster
+    #[arg(long)]
+    pub floaters: Option<i32>,
+    /// Fraction a slot may stray from the target PPH before the scorecard flags it
+    #[arg(long)]
+    pub tolerance: Option<f32>,
+    /// Bridge, Break or ZeroVolume: how slots treat missing aisle numbers
+    #[arg(long)]
+    pub gap_policy: Option<GapPolicy>,
+    /// Station layout file for walking distance estimates
+    #[arg(long, value_name = "PATH")]
+    pub layout: Option<PathBuf>,
+}
+
+impl ConfigOverrides {
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(target_pph) = self.target_pph {
+            config.target_pph = target_pph;
+        }
+        if let Some(target_hc) = self.target_hc {
+            config.target_hc = target_hc;
+        }
+        if let Some(hours) = self.hours {
+            config.total_hours = hours;
+        }
+        if let Some(algorithm) = self.algorithm {
+            config.algorithm = algorithm;
+        }
+        if let Some(min_aisles) = self.min_aisles {
+            config.min_aisle_count = min_aisles;
+        }
+        if let Some(max_aisles) = self.max_aisles {
+            config.max_aisle_count = max_aisles;
+        }
+        if let Some(floater_pph) = self.floater_pph {
+            config.floater_pph = floater_pph;
+        }
+        if let Some(floaters) = self.floaters {
+            config.floaters_per_cluster = floaters;
+        }
+        if let Some(tolerance) = self.tolerance {
+            config.tolerance = tolerance;
+        }
+        if let Some(gap_policy) = self.gap_policy {
+            config.gap_policy = gap_policy;
+        }
+        if let Some(layout) = &self.layout {
+            config.layout = Some(layout.clone());
+        }
+    }
+}
+
+impl InputArgs {
+    /// The file default output paths are named after.
+    pub fn primary(&self) -> &Path {
+        &self.files[0]
+    }
+}
+
+impl PlanArgs {
+    /// The plan files dragged onto the executable get: saved config, default output.
+    pub fn from_files(files: Vec<PathBuf>) -> Self {
+        Self {
+            input: InputArgs {
+                files,
+                ..Default::default()
+            },
+            output: None,
+            sheets: None,
+            change_log: None,
+            format: OutputFormat::default(),
+            interactive: false,
+            assign: Vec::new(),
+            assignments: None,
+            overrides: ConfigOverrides::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Cli {
+        let args = std::iter::once("AMZL-Staffing").chain(args.iter().copied());
+        Cli::try_parse_from(args).unwrap()
+    }
+
+    fn plan_files(cli: &Cli) -> &[PathBuf] {
+        match &cli.command {
+            Some(Command::Plan(args)) => &args.input.files,
+            other => panic!("expected plan, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn no_pause_works_before_and_after_the_subcommand() {
+        for args in [
+            ["--no-pause", "plan", "t.csv"],
+            ["plan", "--no-pause", "t.csv"],
+            ["plan", "t.csv", "--no-pause"],
+        ] {
+            let cli = parse(&args);
+            assert!(cli.no_pause, "{:?}", args);
+            assert_eq!(plan_files(&cli), [PathBuf::from("t.csv")], "{:?}", args);
+            assert!(cli.files.is_empty());
+        }
+        for args in [
+            ["--no-pause", "summary", "t.csv"],
+            ["summary", "t.csv", "--no-pause"],
+        ] {
+            let cli = parse(&args);
+            assert!(cli.no_pause, "{:?}", args);
+            assert!(
+                matches!(&cli.command, Some(Command::Summary { input, .. }) if input.files == [PathBuf::from("t.csv")])
+            );
+        }
+    }
+
+    #[test]
+    fn dropped_files_plan_without_a_subcommand() {
+        for args in [
+            vec!["a.csv", "b.xlsx"],
+            vec!["--no-pause", "a.csv", "b.xlsx"],
+        ] {
+            let cli = parse(&args);
+            assert!(cli.command.is_none());
+            assert_eq!(cli.files, [PathBuf::from("a.csv"), PathBuf::from("b.xlsx")]);
+            assert_eq!(cli.no_pause, args[0] == "--no-pause");
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Checks that a plan gives every aisle on the floor to exactly one stow slot.
+
+use crate::models::{Floor, StowSlot};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// What is wrong with a plan's coverage of the floor. Slots are numbered from 1, as in
+/// the exports.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CoverageReport {
+    /// Aisles on the floor that no slot holds, as `(cluster, aisle number)`.
+    pub missing: Vec<(String, u32)>,
+    /// Aisles held more than once, with the slots that hold them.
+    pub duplicated: Vec<(String, Vec<usize>)>,
+    pub empty_slots: Vec<usize>,
+    /// Slots holding aisles from another cluster than their own.
+    pub cross_cluster: Vec<usize>,
+}
+
+impl CoverageReport {
+    pub fn is_valid(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The missing aisles of `cluster`, named like `A-4`.
+    pub fn missing_in(&self, cluster: &str) -> Vec<String> {
+        self.missing
+            .iter()
+            .filter(|(c, _)| c == cluster)
+            .map(|(c, aisle)| format!("{}-{}", c, aisle))
+            .collect()
+    }
+
+    /// Every missing aisle, named like `A-4`.
+    pub fn missing_labels(&self) -> Vec<String> {
+        self.missing
+            .iter()
+            .map(|(c, aisle)| format!("{}-{}", c, aisle))
+            .collect()
+    }
+}
+
+impl fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let slots = |slots: &[usize]| {
+            slots
+                .iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut problems = Vec::new();
+        if !self.missing.is_empty() {
+            problems.push(format!(
+                "no slot holds {}",
+                self.missing_labels().join(", ")
+            ));
+        }
+        for (aisle, holders) in &self.duplicated {
+            problems.push(format!("{} is in slots {}", aisle, slots(holders)));
+        }
+        if !self.empty_slots.is_empty() {
+            problems.push(format!("empty slots: {}", slots(&self.empty_slots)));
+        }
+        if !self.cross_cluster.is_empty() {
+            problems.push(format!(
+                "slots with aisles of another cluster: {}",
+                slots(&self.cross_cluster)
+            ));
+        }
+        if problems.is_empty() {
+            write!(f, "every aisle is in exactly one slot")
+        } else {
+            write!(f, "{}", problems.join("; "))
+        }
+    }
+}
+
+/// Compares the slots against the floor's aisles.
+pub fn validate_coverage(floor: &Floor, stow_slots: &[StowSlot]) -> CoverageReport {
+    let mut report = CoverageReport::default();
+    let mut holders: BTreeMap<(String, u32), Vec<usize>> = BTreeMap::new();
+    for (i, slot) in stow_slots.iter().enumerate() {
+        if slot.aisles.is_empty() {
+            report.empty_slots.push(i + 1);
+        }
+        if slot.aisles.iter().any(|a| a.cluster != slot.cluster) {
+            report.cross_cluster.push(i + 1);
+        }
+        for aisle in slot.floor_aisles() {
+            holders
+                .entry((aisle.cluster.clone(), aisle.aisle_num))
+                .or_default()
+                .push(i + 1);
+        }
+    }
+    for cluster in &floor.clusters {
+        for aisle in &cluster.aisles {
+            if !holders.contains_key(&(aisle.cluster.clone(), aisle.aisle_num)) {
+                report
+                    .missing
+                    .push((aisle.cluster.clone(), aisle.aisle_num));
+            }
+        }
+    }
+    report.duplicated = holders
+        .into_iter()
+        .filter(|(_, slots)| slots.len() > 1)
+        .map(|((cluster, aisle), slots)| (format!("{}-{}", cluster, aisle), slots))
+        .collect();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::StowSlot;
+    use crate::test_support::{builder, floor, slot};
+
+    fn two_clusters() -> Floor {
+        floor(&[
+            ("A", &[(1, 10), (2, 10), (3, 10), (4, 10)]),
+            ("B", &[(1, 10), (2, 10)]),
+        ])
+    }
+
+    #[test]
+    fn accepts_a_plan_that_covers_every_aisle_once() {
+        let b = builder(
+            two_clusters(),
+            &[("A", &[1, 2]), ("A", &[3, 4]), ("B", &[1, 2])],
+        );
+        let report = b.validate_coverage();
+        assert!(report.is_valid());
+        assert_eq!(report.to_string(), "every aisle is in exactly one slot");
+    }
+
+    #[test]
+    fn reports_missing_aisles() {
+        let b = builder(two_clusters(), &[("A", &[1, 2, 3]), ("B", &[2])]);
+        let report = b.validate_coverage();
+        assert_eq!(report.missing, [("A".to_string(), 4), ("B".to_string(), 1)]);
+        assert!(report.duplicated.is_empty());
+        assert_eq!(report.to_string(), "no slot holds A-4, B-1");
+    }
+
+    #[test]
+    fn reports_duplicated_aisles() {
+        let b = builder(
+            two_clusters(),
+            &[
+                ("A", &[1, 2]),
+                ("A", &[2, 3, 4]),
+                ("B", &[1, 2]),
+                ("B", &[2]),
+            ],
+        );
+        let report = b.validate_coverage();
+        assert_eq!(
+            report.duplicated,
+            [
+                ("A-2".to_string(), vec![1, 2]),
+                ("B-2".to_string(), vec![3, 4])
+            ]
+        );
+        assert!(report.missing.is_empty());
+        assert_eq!(
+            report.to_string(),
+            "A-2 is in slots 1, 2; B-2 is in slots 3, 4"
+        );
+    }
+
+    #[test]
+    fn reports_empty_slots() {
+        let mut b = builder(two_clusters(), &[("A", &[1, 2, 3, 4]), ("B", &[1, 2])]);
+        b.stow_slots
+            .insert(1, StowSlot::new("A".to_string(), Vec::new(), 1.0));
+        let report = b.validate_coverage();
+        assert_eq!(report.empty_slots, [2]);
+        assert!(!report.is_valid());
+    }
+
+    #[test]
+    fn reports_slots_with_aisles_of_another_cluster() {
+        let mut b = builder(two_clusters(), &[("A", &[1, 2, 3, 4]), ("B", &[1])]);
+        let mut stray = slot(&b, "B", &[2]);
+        stray.cluster = "A".to_string();
+        b.stow_slots.push(stray);
+        let report = b.validate_coverage();
+        assert_eq!(report.cross_cluster, [3]);
+        assert!(report.missing.is_empty());
+        assert_eq!(
+            report.to_string(),
+            "slots with aisles of another cluster: 3"
+        );
+    }
+}
@@ -0,0 +1,641 @@
+//! Hand edits to a finished plan. Every operation checks that the slots it touches stay in
+//! one cluster, unlocked, consecutive and within the aisle pair limits of the plan before
+//! anything changes, so a rejected edit leaves the plan as it was.
+
+use crate::models::{Aisle, Algorithm, StowSlot, StowSlotBuilder};
+use crate::partition::PartLimits;
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditError {
+    /// There is no stow slot with this index.
+    NoSuchSlot(usize),
+    /// No slot holds this aisle.
+    NoSuchAisle(String),
+    Locked(String),
+    DifferentClusters(String, String),
+    /// The edit would leave the slot with no aisles.
+    Empty(String),
+    /// The slot's aisles would no longer be consecutive.
+    NotConsecutive(String),
+    TooFewPairs {
+        slot: String,
+        min: usize,
+    },
+    TooManyPairs {
+        slot: String,
+        max: usize,
+    },
+    /// A split position outside the slot.
+    InvalidSplit {
+        slot: String,
+        at: usize,
+    },
+    /// The slot has no unlocked neighbour in its cluster to take its aisles.
+    NoNeighbour(String),
+    /// A boundary move of zero aisles.
+    NoMove(String),
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditError::NoSuchSlot(index) => write!(f, "there is no stow slot {}", index + 1),
+            EditError::NoSuchAisle(aisle) => write!(f, "no stow slot holds aisle {}", aisle),
+            EditError::Locked(slot) => write!(f, "{} is locked", slot),
+            EditError::DifferentClusters(a, b) => {
+                write!(f, "{} and {} are in different clusters", a, b)
+            }
+            EditError::Empty(slot) => write!(f, "{} would have no aisles left", slot),
+            EditError::NotConsecutive(slot) => {
+                write!(f, "{} would no longer be consecutive aisles", slot)
+            }
+            EditError::TooFewPairs { slot, min } => {
+                write!(f, "{} would have fewer than {} aisle pairs", slot, min)
+            }
+            EditError::TooManyPairs { slot, max } => {
+                write!(f, "{} would have more than {} aisle pairs", slot, max)
+            }
+            EditError::InvalidSplit { slot, at } => {
+                write!(f, "{} cannot be split after aisle {}", slot, at)
+            }
+            EditError::NoNeighbour(slot) => {
+                write!(f, "{} has no unlocked neighbour to take its aisles", slot)
+            }
+            EditError::NoMove(slot) => {
+                write!(
+                    f,
+                    "moving the boundary of {} by 0 aisles changes nothing",
+                    slot
+                )
+            }
+        }
+    }
+}
+
+impl Error for EditError {}
+
+impl StowSlotBuilder {
+    /// Shifts the boundary between slot `index` and the slot after it by `delta` aisles;
+    /// positive moves it right, handing the next slot's first aisles to slot `index`.
+    pub fn move_boundary(&mut self, index: usize, delta: i32) -> Result<(), EditError> {
+        self.editable_neighbours(index)?;
+        if delta == 0 {
+            return Err(EditError::NoMove(self.stow_slots[index].display_range()));
+        }
+        let mut left = self.stow_slots[index].clone();
+        let mut right = self.stow_slots[index + 1].clone();
+        for _ in 0..delta.unsigned_abs() {
+            let (from, to) = if delta > 0 {
+                (&mut right, &mut left)
+            } else {
+                (&mut left, &mut right)
+            };
+            if from.aisles.len() < 2 {
+                return Err(EditError::Empty(from.display_range()));
+            }
+            if delta > 0 {
+                to.aisles.push(from.aisles.remove(0));
+            } else if let Some(aisle) = from.aisles.pop() {
+                to.aisles.insert(0, aisle);
+            }
+        }
+        let description = format!(
+            "Moved the boundary between {} and {} {} aisle{} {}",
+            self.stow_slots[index].display_range(),
+            self.stow_slots[index + 1].display_range(),
+            delta.unsigned_abs(),
+            if delta.unsigned_abs() == 1 { "" } else { "s" },
+            if delta > 0 { "right" } else { "left" }
+        );
+        self.replace_slots(index..index + 2, vec![left, right], description)
+    }
+
+    /// Splits slot `index` into two, the second starting at its `at`-th aisle. The first half
+    /// keeps the slot's stower; the second half has none.
+    pub fn split_slot(&mut self, index: usize, at: usize) -> Result<(), EditError> {
+        let slot = self.unlocked_slot(index)?;
+        if at == 0 || at >= slot.aisles.len() {
+            return Err(EditError::InvalidSplit {
+                slot: slot.display_range(),
+                at,
+            });
+        }
+        let description = format!(
+            "Split {} after {}",
+            slot.display_range(),
+            slot.aisles[at - 1].display_aisle()
+        );
+        let mut first = slot.clone();
+        let mut second = slot.clone();
+        second.aisles = first.aisles.split_off(at);
+        second.stower = None;
+        self.replace_slots(index..index + 1, vec![first, second], description)
+    }
+
+    /// Merges the slot after `index` into slot `index`. The merged slot keeps the first
+    /// slot's stower, or the second's if the first has none.
+    pub fn merge_slots(&mut self, index: usize) -> Result<(), EditError> {
+        self.editable_neighbours(index)?;
+        let next = &self.stow_slots[index + 1];
+        let mut merged = self.stow_slots[index].clone();
+        merged.aisles.extend(next.aisles.iter().cloned());
+        merged.stower = merged.stower.or_else(|| next.stower.clone());
+        merged.is_floater = false;
+        let description = format!(
+            "Merged {} and {}",
+            self.stow_slots[index].display_range(),
+            self.stow_slots[index + 1].display_range()
+        );
+        self.replace_slots(index..index + 2, vec![merged], description)
+    }
+
+    /// Removes slot `index` and hands its aisles to the unlocked slots on either side, split
+    /// where the two end up closest in workload.
+    pub fn remove_slot(&mut self, index: usize) -> Result<(), EditError> {
+        let slot = self.unlocked_slot(index)?.clone();
+        let description = format!("Removed {}", slot.display_range());
+        let neighbour = |i: Option<usize>| {
+            i.and_then(|i| self.stow_slots.get(i))
+                .filter(|s| s.cluster == slot.cluster && !s.locked)
+                .cloned()
+        };
+        let before = neighbour(index.checked_sub(1));
+        let after = neighbour(Some(index + 1));
+        let (start, end) = (
+            index - usize::from(before.is_some()),
+            index + 1 + usize::from(after.is_some()),
+        );
+        let slots = match (before, after) {
+            (None, None) => return Err(EditError::NoNeighbour(slot.display_range())),
+            (Some(mut before), None) => {
+                before.aisles.extend(slot.aisles);
+                vec![before]
+            }
+            (None, Some(mut after)) => {
+                after.aisles.splice(0..0, slot.aisles);
+                vec![after]
+            }
+            (Some(mut before), Some(mut after)) => {
+                let at = (0..=slot.aisles.len())
+                    .min_by(|&a, &b| {
+                        let gap = |at: usize| {
+                            let (head, tail) = slot.aisles.split_at(at);
+                            let load = |aisles: &[Rc<Aisle>]| {
+                                aisles.iter().map(|a| a.workload()).sum::<f32>()
+                            };
+                            (before.workload() + load(head) - after.workload() - load(tail)).abs()
+                        };
+                        gap(a).total_cmp(&gap(b))
+                    })
+                    .unwrap_or(0);
+                let (head, tail) = slot.aisles.split_at(at);
+                before.aisles.extend(head.iter().cloned());
+                after.aisles.splice(0..0, tail.iter().cloned());
+                vec![before, after]
+            }
+        };
+        self.replace_slots(start..end, slots, description)
+    }
+
+    /// Moves one aisle into slot `to`. Both slots have to stay consecutive, so in practice
+    /// this hands over an aisle at the edge between neighbouring slots.
+    pub fn reassign_aisle(
+        &mut self,
+        cluster: &str,
+        aisle_num: u32,
+        to: usize,
+    ) -> Result<(), EditError> {
+        let label = format!("{}-{}", cluster, aisle_num);
+        let from = self
+            .stow_slots
+            .iter()
+            .position(|s| s.cluster == cluster && s.aisles.iter().any(|a| a.aisle_num == aisle_num))
+            .ok_or(EditError::NoSuchAisle(label))?;
+        if from == to {
+            return Ok(());
+        }
+        let source = self.unlocked_slot(from)?.clone();
+        let target = self.unlocked_slot(to)?.clone();
+        if source.cluster != target.cluster {
+            return Err(EditError::DifferentClusters(
+                source.display_range(),
+                target.display_range(),
+            ));
+        }
+        let mut source = source;
+        let mut target = target;
+        let position = source
+            .aisles
+            .iter()
+            .position(|a| a.aisle_num == aisle_num)
+            .unwrap_or_default();
+        let aisle = source.aisles.remove(position);
+        if source.aisles.is_empty() {
+            return Err(EditError::Empty(self.stow_slots[from].display_range()));
+        }
+        let at = target
+            .aisles
+            .iter()
+            .position(|a| a.aisle_num > aisle_num)
+            .unwrap_or(target.aisles.len());
+        target.aisles.insert(at, aisle);
+        self.check_slot(&self.stow_slots[from], &source)?;
+        self.check_slot(&self.stow_slots[to], &target)?;
+        let description = format!(
+            "Moved {}-{} from {} to {}",
+            cluster,
+            aisle_num,
+            self.stow_slots[from].display_range(),
+            self.stow_slots[to].display_range()
+        );
+        let before = self.snapshot();
+        for (index, slot) in [(from, source), (to, target)] {
+            self.stow_slots[index] = slot;
+            self.stow_slots[index].update_pph();
+        }
+        self.update_floater_flags();
+        self.update_walk_distances();
+        self.record(description, before);
+        Ok(())
+    }
+
+    fn unlocked_slot(&self, index: usize) -> Result<&StowSlot, EditError> {
+        let slot = self
+            .stow_slots
+            .get(index)
+            .ok_or(EditError::NoSuchSlot(index))?;
+        if slot.locked {
+            return Err(EditError::Locked(slot.display_range()));
+        }
+        Ok(slot)
+    }
+
+    /// Checks that slot `index` and the one after it exist, share a cluster and are unlocked.
+    fn editable_neighbours(&self, index: usize) -> Result<(), EditError> {
+        let slot = self.unlocked_slot(index)?;
+        let next = self.unlocked_slot(index + 1)?;
+        if slot.cluster != next.cluster {
+            return Err(EditError::DifferentClusters(
+                slot.display_range(),
+                next.display_range(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Swaps `range` of the plan for `slots` once every new slot passes `check_slot`
+    /// against the slots it came from, and records the change.
+    fn replace_slots(
+        &mut self,
+        range: std::ops::Range<usize>,
+        mut slots: Vec<StowSlot>,
+        description: String,
+    ) -> Result<(), EditError> {
+        for slot in &slots {
+            // The old slot it shares the most aisles with.
+            let before = self.stow_slots[range.clone()]
+                .iter()
+                .max_by_key(|old| {
+                    old.aisles
+                        .iter()
+                        .filter(|a| slot.aisles.iter().any(|b| b.aisle_num == a.aisle_num))
+                        .count()
+                })
+                .unwrap_or(&self.stow_slots[range.start]);
+            self.check_slot(before, slot)?;
+        }
+        for slot in &mut slots {
+            slot.update_pph();
+        }
+        let before = self.snapshot();
+        self.stow_slots.splice(range, slots);
+        self.update_floater_flags();
+        self.update_walk_distances();
+        self.record(description, before);
+        Ok(())
+    }
+
+    /// Rejects a slot that an edit made empty, non-consecutive, or pushed outside the aisle
+    /// pair limits. Slots that already broke a rule may keep breaking it, so floaters and
+    /// hand-built slots stay editable.
+    fn check_slot(&self, before: &StowSlot, after: &StowSlot) -> Result<(), EditError> {
+        let label = || after.display_range();
+        if after.aisles.is_empty() {
+            return Err(EditError::Empty(before.display_range()));
+        }
+        if before.is_consecutive() && !after.is_consecutive() {
+            return Err(EditError::NotConsecutive(label()));
+        }
+        let Some(config) = self
+            .algorithm_config
+            .as_ref()
+            .filter(|c| c.algorithm != Algorithm::TargetPPH)
+        else {
+            return Ok(());
+        };
+        let PartLimits { min, max } = config.pair_limits();
+        let (old, new) = (self.pair_count(before), self.pair_count(after));
+        if new < min && new < old {
+            return Err(EditError::TooFewPairs { slot: label(), min });
+        }
+        if new > max && new > old {
+            return Err(EditError::TooManyPairs { slot: label(), max });
+        }
+        Ok(())
+    }
+
+    /// How many of its cluster's aisle pairs the slot touches.
+    pub(crate) fn pair_count(&self, slot: &StowSlot) -> usize {
+        self.ensure_aisle_pairs();
+        let floor = self.floor();
+        let Some(cluster) = floor.get_cluster(&slot.cluster) else {
+            return slot.aisles.len();
+        };
+        let mut pairs: Vec<usize> = slot
+            .aisles
+            .iter()
+            .map(|aisle| {
+                cluster
+                    .aisle_pairs
+                    .iter()
+                    .position(|pair| {
+                        cluster
+                            .get_aisles_from_pair(pair)
+                            .iter()
+                            .any(|a| a.aisle_num == aisle.aisle_num)
+                    })
+                    .unwrap_or(usize::MAX - aisle.aisle_num as usize)
+            })
+            .collect();
+        pairs.sort_unstable();
+        pairs.dedup();
+        pairs.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AlgorithmConfig;
+    use crate::test_support::{builder, floor, plan};
+
+    /// Cluster A with aisles 1-8, aisle `n` holding `10 * n` packages.
+    fn cluster_a(slots: &[(&str, &[u32])]) -> StowSlotBuilder {
+        let aisles: Vec<(u32, i32)> = (1..=8).map(|n| (n, 10 * n as i32)).collect();
+        builder(floor(&[("A", &aisles)]), slots)
+    }
+
+    fn with_limits(mut builder: StowSlotBuilder, min: i32, max: i32) -> StowSlotBuilder {
+        builder.algorithm_config = Some(AlgorithmConfig {
+            algorithm: Algorithm::Balanced,
+            min_aisle_count: min,
+            max_aisle_count: max,
+            ..Default::default()
+        });
+        builder
+    }
+
+    fn pph(builder: &StowSlotBuilder) -> Vec<f32> {
+        builder.stow_slots.iter().map(|s| s.pph).collect()
+    }
+
+    /// Checks that a rejected edit left the plan and its history alone.
+    fn assert_unchanged(builder: &StowSlotBuilder, expected: &[&[u32]]) {
+        assert_eq!(plan(builder), expected);
+        assert!(builder.change_log().is_empty());
+    }
+
+    #[test]
+    fn move_boundary_recomputes_pph() {
+        let mut b = cluster_a(&[("A", &[1, 2, 3, 4]), ("A", &[5, 6, 7, 8])]);
+        b.move_boundary(0, 2).unwrap();
+        assert_eq!(plan(&b), [vec![1, 2, 3, 4, 5, 6], vec![7, 8]]);
+        assert_eq!(pph(&b), [210.0, 150.0]);
+
+        b.move_boundary(0, -3).unwrap();
+        assert_eq!(plan(&b), [vec![1, 2, 3], vec![4, 5, 6, 7, 8]]);
+        assert_eq!(pph(&b), [60.0, 300.0]);
+        assert_eq!(b.change_log().len(), 2);
+    }
+
+    #[test]
+    fn split_and_merge_recompute_pph() {
+        let mut b = cluster_a(&[("A", &[1, 2, 3, 4])]);
+        b.split_slot(0, 1).unwrap();
+        assert_eq!(plan(&b), [vec![1], vec![2, 3, 4]]);
+        assert_eq!(pph(&b), [10.0, 90.0]);
+
+        b.merge_slots(0).unwrap();
+        assert_eq!(plan(&b), [vec![1, 2, 3, 4]]);
+        assert_eq!(pph(&b), [100.0]);
+    }
+
+    #[test]
+    fn remove_slot_hands_aisles_to_both_neighbours() {
+        let mut b = cluster_a(&[("A", &[1, 2, 3]), ("A", &[4, 5]), ("A", &[6, 7, 8])]);
+        b.remove_slot(1).unwrap();
+        // 60 + 40 + 50 = 150 against 210 is the closest split.
+        assert_eq!(plan(&b), [vec![1, 2, 3, 4, 5], vec![6, 7, 8]]);
+        assert_eq!(pph(&b), [150.0, 210.0]);
+    }
+
+    #[test]
+    fn reassign_aisle_moves_an_edge_aisle() {
+        let mut b = cluster_a(&[("A", &[1, 2, 3, 4]), ("A", &[5, 6, 7, 8])]);
+        b.reassign_aisle("A", 4, 1).unwrap();
+        assert_eq!(plan(&b), [vec![1, 2, 3], vec![4, 5, 6, 7, 8]]);
+        assert_eq!(pph(&b), [60.0, 300.0]);
+    }
+
+    #[test]
+    fn split_clears_the_stower_of_the_second_half() {
+        let mut b = cluster_a(&[("A", &[1, 2, 3, 4])]);
+        b.stow_slots[0].stower = Some("Sam".to_string());
+        b.split_slot(0, 2).unwrap();
+        assert_eq!(b.stow_slots[0].stower.as_deref(), Some("Sam"));
+        assert_eq!(b.stow_slots[1].stower, None);
+    }
+
+    #[test]
+    fn merge_keeps_one_stower() {
+        let mut b = cluster_a(&[("A", &[1, 2]), ("A", &[3, 4]), ("A", &[5, 6])]);
+        b.stow_slots[1].stower = Some("Ana".to_string());
+        b.stow_slots[2].stower = Some("Sam".to_string());
+        b.merge_slots(1).unwrap();
+        assert_eq!(b.stow_slots[1].stower.as_deref(), Some("Ana"));
+        b.merge_slots(0).unwrap();
+        assert_eq!(b.stow_slots[0].stower.as_deref(), Some("Ana"));
+    }
+
+    #[test]
+    fn edits_update_the_floater_flag() {
+        let f = floor(&[(
+            "A",
+            &[
+                (1, 10),
+                (2, 20),
+                (3, 30),
+                (4, 40),
+                (5, 100),
+                (6, 100),
+                (7, 10),
+                (8, 10),
+            ],
+        )]);
+        let mut b = builder(f, &[("A", &[1, 2, 3, 4, 5, 6, 7, 8])]);
+        b.algorithm_config = Some(AlgorithmConfig {
+            floater_pph: 150.0,
+            ..Default::default()
+        });
+        b.split_slot(0, 4).unwrap();
+        let floaters = |b: &StowSlotBuilder| -> Vec<bool> {
+            b.stow_slots.iter().map(|s| s.is_floater).collect()
+        };
+        assert_eq!(floaters(&b), [true, false]);
+        b.move_boundary(0, 2).unwrap();
+        assert_eq!(floaters(&b), [false, true]);
+        let _ = b.undo();
+        assert_eq!(floaters(&b), [true, false]);
+        b.merge_slots(0).unwrap();
+        assert_eq!(floaters(&b), [false]);
+    }
+
+    #[test]
+    fn rejects_moving_a_boundary_by_nothing() {
+        let mut b = cluster_a(&[("A", &[1, 2, 3, 4]), ("A", &[5, 6, 7, 8])]);
+        assert_eq!(
+            b.move_boundary(0, 0),
+            Err(EditError::NoMove("A-1 - A-4".into()))
+        );
+        assert_unchanged(&b, &[&[1, 2, 3, 4], &[5, 6, 7, 8]]);
+    }
+
+    #[test]
+    fn rejects_locked_slots() {
+        let mut b = cluster_a(&[("A", &[1, 2, 3, 4]), ("A", &[5, 6, 7, 8])]);
+        b.set_slot_locked(1, true).unwrap();
+        let before = b.change_log().len();
+        let locked = EditError::Locked("A-5 - A-8".to_string());
+        assert_eq!(b.move_boundary(0, 1), Err(locked.clone()));
+        assert_eq!(b.merge_slots(0), Err(locked.clone()));
+        assert_eq!(b.split_slot(1, 2), Err(locked.clone()));
+        assert_eq!(b.remove_slot(1), Err(locked.clone()));
+        assert_eq!(b.reassign_aisle("A", 5, 0), Err(locked));
+        assert_eq!(plan(&b), [vec![1, 2, 3, 4], vec![5, 6, 7, 8]]);
+        assert_eq!(b.change_log().len(), before);
+    }
+
+    #[test]
+    fn rejects_edits_across_clusters() {
+        let f = floor(&[("A", &[(1, 10), (2, 10)]), ("B", &[(1, 10), (2, 10)])]);
+        let mut b = builder(f, &[("A", &[1, 2]), ("B", &[1, 2])]);
+        let clusters = EditError::DifferentClusters("A-1 - A-2".into(), "B-1 - B-2".into());
+        assert_eq!(b.merge_slots(0), Err(clusters.clone()));
+        assert_eq!(b.move_boundary(0, 1), Err(clusters.clone()));
+        assert_eq!(b.reassign_aisle("A", 2, 1), Err(clusters));
+        assert_eq!(
+            b.remove_slot(0),
+            Err(EditError::NoNeighbour("A-1 - A-2".into()))
+        );
+        assert_unchanged(&b, &[&[1, 2], &[1, 2]]);
+    }
+
+    #[test]
+    fn rejects_non_consecutive_slots() {
+        let mut b = cluster_a(&[("A", &[1, 2, 3]), ("A", &[4, 5]), ("A", &[6, 7, 8])]);
+        assert_eq!(
+            b.reassign_aisle("A", 3, 2),
+            Err(EditError::NotConsecutive("A-3 - A-8".into()))
+        );
+        assert_eq!(
+            b.reassign_aisle("A", 2, 1),
+            Err(EditError::NotConsecutive("A-1 - A-3".into()))
+        );
+        assert_unchanged(&b, &[&[1, 2, 3], &[4, 5], &[6, 7, 8]]);
+    }
+
+    #[test]
+    fn rejects_slots_outside_the_pair_limits() {
+        let slots: &[(&str, &[u32])] = &[("A", &[1, 2, 3, 4]), ("A", &[5, 6, 7, 8])];
+        let mut b = with_limits(cluster_a(slots), 2, 2);
+        assert_eq!(
+            b.move_boundary(0, -2),
+            Err(EditError::TooFewPairs {
+                slot: "A-1 - A-2".into(),
+                min: 2
+            })
+        );
+        assert_eq!(
+            b.move_boundary(0, 2),
+            Err(EditError::TooManyPairs {
+                slot: "A-1 - A-6".into(),
+                max: 2
+            })
+        );
+        assert_eq!(
+            b.split_slot(0, 2),
+            Err(EditError::TooFewPairs {
+                slot: "A-1 - A-2".into(),
+                min: 2
+            })
+        );
+        assert_eq!(
+            b.merge_slots(0),
+            Err(EditError::TooManyPairs {
+                slot: "A-1 - A-8".into(),
+                max: 2
+            })
+        );
+        assert_unchanged(&b, &[&[1, 2, 3, 4], &[5, 6, 7, 8]]);
+    }
+
+    #[test]
+    fn slots_already_outside_the_limits_stay_editable() {
+        let slots: &[(&str, &[u32])] = &[("A", &[1, 2]), ("A", &[3, 4, 5, 6, 7, 8])];
+        let mut b = with_limits(cluster_a(slots), 2, 2);
+        b.move_boundary(0, 2).unwrap();
+        assert_eq!(plan(&b), [vec![1, 2, 3, 4], vec![5, 6, 7, 8]]);
+    }
+
+    #[test]
+    fn rejects_empty_slots() {
+        let mut b = cluster_a(&[("A", &[1, 2]), ("A", &[3]), ("A", &[4, 5, 6, 7, 8])]);
+        assert_eq!(
+            b.move_boundary(0, 1),
+            Err(EditError::Empty("A-3 - A-3".into()))
+        );
+        assert_eq!(
+            b.move_boundary(1, -1),
+            Err(EditError::Empty("A-3 - A-3".into()))
+        );
+        assert_eq!(
+            b.reassign_aisle("A", 3, 0),
+            Err(EditError::Empty("A-3 - A-3".into()))
+        );
+        for at in [0, 2] {
+            assert_eq!(
+                b.split_slot(0, at),
+                Err(EditError::InvalidSplit {
+                    slot: "A-1 - A-2".into(),
+                    at
+                })
+            );
+        }
+        assert_unchanged(&b, &[&[1, 2], &[3], &[4, 5, 6, 7, 8]]);
+    }
+
+    #[test]
+    fn rejects_unknown_slots_and_aisles() {
+        let mut b = cluster_a(&[("A", &[1, 2, 3, 4]), ("A", &[5, 6, 7, 8])]);
+        assert_eq!(b.merge_slots(1), Err(EditError::NoSuchSlot(2)));
+        assert_eq!(b.split_slot(2, 1), Err(EditError::NoSuchSlot(2)));
+        assert_eq!(
+            b.reassign_aisle("A", 9, 0),
+            Err(EditError::NoSuchAisle("A-9".into()))
+        );
+        assert_unchanged(&b, &[&[1, 2, 3, 4], &[5, 6, 7, 8]]);
+    }
+}
@@ -0,0 +1,301 @@
+use crate::models::{AlgorithmConfig, PlanningContext, StowSlotBuilder};
+use crate::scorecard::Scorecard;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// One row of the exported stow slot plan.
+#[derive(Debug, Serialize)]
+pub struct StowSlotRow {
+    #[serde(rename = "Slot")]
+    pub slot: usize,
+    #[serde(rename = "Cluster")]
+    pub cluster: String,
+    #[serde(rename = "First Aisle")]
+    pub first_aisle: String,
+    #[serde(rename = "Last Aisle")]
+    pub last_aisle: String,
+    #[serde(rename = "Aisles")]
+    pub aisles: String,
+    #[serde(rename = "Packages")]
+    pub packages: i32,
+    #[serde(rename = "Bags")]
+    pub bags: i32,
+    #[serde(rename = "PPH")]
+    pub pph: i32,
+    #[serde(rename = "Workload")]
+    pub workload: i32,
+    #[serde(rename = "Weighted PPH")]
+    pub weighted_pph: i32,
+    #[serde(rename = "Walk Distance")]
+    pub walk_distance: i32,
+    #[serde(rename = "Gaps")]
+    pub gaps: String,
+    #[serde(rename = "Floater")]
+    pub floater: bool,
+    #[serde(rename = "Locked")]
+    pub locked: bool,
+    #[serde(rename = "Stower")]
+    pub stower: String,
+}
+
+impl StowSlotBuilder {
+    pub fn stow_slot_rows(&self) -> Vec<StowSlotRow> {
+        self.stow_slots
+            .iter()
+            .enumerate()
+            .map(|(i, slot)| StowSlotRow {
+                slot: i + 1,
+                cluster: slot.cluster.clone(),
+                first_aisle: slot
+                    .floor_aisles()
+                    .next()
+                    .map(|a| a.display_aisle())
+                    .unwrap_or_default(),
+                last_aisle: slot
+                    .floor_aisles()
+                    .last()
+                    .map(|a| a.display_aisle())
+                    .unwrap_or_default(),
+                aisles: slot
+                    .floor_aisles()
+                    .map(|a| a.display_aisle())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                packages: slot.total_packages(),
+                bags: slot.total_bags(),
+                pph: slot.pph as i32,
+                workload: slot.workload().round() as i32,
+                weighted_pph: slot.weighted_pph as i32,
+                walk_distance: slot.walk_distance.round() as i32,
+                gaps: slot.gap_labels().join(", "),
+                floater: slot.is_floater,
+                locked: slot.locked,
+                stower: slot.stower.clone().unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// Writes one CSV row per stow slot, ready to paste into a spreadsheet.
+    pub fn export_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut writer = csv::Writer::from_path(path)?;
+        for row in self.stow_slot_rows() {
+            writer.serialize(row)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// `plan.csv` becomes `plan_stow_slots.csv` in the same directory.
+pub fn default_export_path(input: &Path) -> PathBuf {
+    let stem = input
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "plan".to_string());
+    input.with_file_name(format!("{}_stow_slots.csv", stem))
+}
+
+/// Version of the JSON plan layout. Bump it whenever a field is renamed or removed.
+pub const PLAN_FORMAT_VERSION: u32 = 1;
+
+/// A completed plan in a stable, serializable form.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlanDocument {
+    pub version: u32,
+    pub inputs: InputSummary,
+    pub context: PlanningContext,
+    pub config: Option<AlgorithmConfig>,
+    pub slots: Vec<SlotDocument>,
+    pub headcount_allocation: Option<Vec<ClusterHeadcountDocument>>,
+    /// Set by callers that know the tolerance to score the plan with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scorecard: Option<Scorecard>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InputSummary {
+    pub clusters: usize,
+    pub aisles: usize,
+    pub total_packages: i32,
+    pub total_bags: i32,
+    pub total_workload: f32,
+    pub pph: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SlotDocument {
+    pub slot: usize,
+    pub cluster: String,
+    pub aisles: Vec<AisleDocument>,
+    pub packages: i32,
+    pub bags: i32,
+    pub pph: f32,
+    pub workload: f32,
+    pub weighted_pph: f32,
+    pub walk_distance: f32,
+    /// Aisle numbers inside the slot's range that it skips.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gaps: Vec<u32>,
+    pub floater: bool,
+    pub locked: bool,
+    /// The stower the slot was pre-assigned to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stower: Option<String>,
+    /// PPH per input file, present when the plan was built with sources kept apart.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<SourceDocument>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SourceDocument {
+    pub source: String,
+    pub packages: i32,
+    pub pph: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AisleDocument {
+    pub aisle: u32,
+    pub packages: i32,
+    pub bags: i32,
+    pub workload: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClusterHeadcountDocument {
+    pub cluster: String,
+    pub total_packages: i32,
+    pub total_bags: i32,
+    pub workload: f32,
+    pub quota: f32,
+    pub headcount: usize,
+    pub min_pph: f32,
+    pub max_pph: f32,
+    pub mean_pph: f32,
+}
+
+impl StowSlotBuilder {
+    pub fn plan_document(&self) -> PlanDocument {
+        let floor = self.floor();
+        let inputs = InputSummary {
+            clusters: floor.clusters.len(),
+            aisles: floor.clusters.iter().map(|c| c.aisles.len()).sum(),
+            total_packages: floor.get_total_packages(),
+            total_bags: floor
+                .clusters
+                .iter()
+                .flat_map(|c| c.aisles.iter())
+                .map(|a| a.total_bags())
+                .sum(),
+            total_workload: floor.get_total_workload(),
+            pph: floor.packages_per_hour(),
+        };
+        let slots = self
+            .stow_slots
+            .iter()
+            .enumerate()
+            .map(|(i, slot)| SlotDocument {
+                slot: i + 1,
+                cluster: slot.cluster.clone(),
+                aisles: slot
+                    .floor_aisles()
+                    .map(|a| AisleDocument {
+                        aisle: a.aisle_num,
+                        packages: a.total_packages(),
+                        bags: a.total_bags(),
+                        workload: a.workload(),
+                    })
+                    .collect(),
+                packages: slot.total_packages(),
+                bags: slot.total_bags(),
+                pph: slot.pph,
+                workload: slot.workload(),
+                weighted_pph: slot.weighted_pph,
+                walk_distance: slot.walk_distance,
+                gaps: slot.gaps(),
+                floater: slot.is_floater,
+                locked: slot.locked,
+                stower: slot.stower.clone(),
+                sources: slot
+                    .packages_by_source()
+                    .into_iter()
+                    .map(|(source, packages)| SourceDocument {
+                        source,
+                        packages,
+                        pph: packages as f32 / slot.total_hours,
+                    })
+                    .collect(),
+            })
+            .collect();
+        let headcount_allocation = self.headcount_allocation.as_ref().map(|allocation| {
+            allocation
+                .clusters
+                .iter()
+                .map(|c| ClusterHeadcountDocument {
+                    cluster: c.cluster.clone(),
+                    total_packages: c.total_packages,
+                    total_bags: c.total_bags,
+                    workload: c.workload,
+                    quota: c.quota,
+                    headcount: c.headcount,
+                    min_pph: c.min_pph,
+                    max_pph: c.max_pph,
+                    mean_pph: c.mean_pph,
+                })
+                .collect()
+        });
+
+        PlanDocument {
+            version: PLAN_FORMAT_VERSION,
+            inputs,
+            context: floor.context.clone(),
+            config: self.algorithm_config.clone(),
+            slots,
+            headcount_allocation,
+            scorecard: None,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&self.plan_document())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Algorithm, Assignment};
+    use crate::test_support::floor;
+
+    #[test]
+    fn json_plans_read_back_unchanged() {
+        let a: Vec<(u32, i32)> = (1..=8).map(|n| (n, 10 * n as i32)).collect();
+        let b: Vec<(u32, i32)> = [1, 2, 5, 6].map(|n| (n, 25)).to_vec();
+        let mut builder = floor(&[("A", &a), ("B", &b)]).create_stow_slot_builder();
+        builder
+            .assign(&Assignment::new("Sam", "A-1..A-2").unwrap())
+            .unwrap();
+        builder
+            .start_algorithm(AlgorithmConfig {
+                algorithm: Algorithm::Proportional,
+                target_hc: 4,
+                min_aisle_count: 1,
+                max_aisle_count: 3,
+                ..Default::default()
+            })
+            .unwrap();
+        let mut document = builder.plan_document();
+        document.scorecard = Some(Scorecard::new(&builder, 0.1));
+        let json = serde_json::to_string_pretty(&document).unwrap();
+
+        let read: PlanDocument = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.version, PLAN_FORMAT_VERSION);
+        assert_eq!(read.slots.len(), builder.stow_slots.len());
+        assert_eq!(read.slots[0].stower.as_deref(), Some("Sam"));
+        assert!(read.slots[0].locked);
+        assert!(read.headcount_allocation.is_some());
+        assert!(read.scorecard.is_some());
+        assert_eq!(serde_json::to_string_pretty(&read).unwrap(), json);
+    }
+}
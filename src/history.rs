@@ -0,0 +1,171 @@
+//! Undo and redo for a plan. Every change to a `StowSlotBuilder` (algorithm runs,
+//! assignments, added slots, locks and hand edits) is recorded with a one-line
+//! description, so the finished plan comes with the list of steps that produced it.
+
+use crate::models::{AlgorithmConfig, HeadcountAllocation, StowSlot, StowSlotBuilder};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/// The plan as it stood before or after a change.
+#[derive(Debug, Clone)]
+pub(crate) struct Snapshot {
+    stow_slots: Vec<StowSlot>,
+    locked_aisles: HashSet<(String, u32)>,
+    headcount_allocation: Option<HeadcountAllocation>,
+    algorithm_config: Option<AlgorithmConfig>,
+}
+
+#[derive(Debug, Clone)]
+struct Change {
+    description: String,
+    before: Snapshot,
+    after: Snapshot,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct History {
+    done: Vec<Change>,
+    undone: Vec<Change>,
+}
+
+impl StowSlotBuilder {
+    pub(crate) fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            stow_slots: self.stow_slots.clone(),
+            locked_aisles: self.locked_aisles.clone(),
+            headcount_allocation: self.headcount_allocation.clone(),
+            algorithm_config: self.algorithm_config.clone(),
+        }
+    }
+
+    pub(crate) fn restore(&mut self, snapshot: Snapshot) {
+        self.stow_slots = snapshot.stow_slots;
+        self.locked_aisles = snapshot.locked_aisles;
+        self.headcount_allocation = snapshot.headcount_allocation;
+        self.algorithm_config = snapshot.algorithm_config;
+    }
+
+    /// Adds a finished change to the history; anything undone can no longer be redone.
+    pub(crate) fn record(&mut self, description: String, before: Snapshot) {
+        let after = self.snapshot();
+        self.history.done.push(Change {
+            description,
+            before,
+            after,
+        });
+        self.history.undone.clear();
+    }
+
+    /// Reverts the last change and returns its description.
+    pub fn undo(&mut self) -> Option<String> {
+        let change = self.history.done.pop()?;
+        self.restore(change.before.clone());
+        let description = change.description.clone();
+        self.history.undone.push(change);
+        Some(description)
+    }
+
+    /// Applies the last undone change again and returns its description.
+    pub fn redo(&mut self) -> Option<String> {
+        let change = self.history.undone.pop()?;
+        self.restore(change.after.clone());
+        let description = change.description.clone();
+        self.history.done.push(change);
+        Some(description)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.history.undone.is_empty()
+    }
+
+    /// The changes behind the current plan, oldest first.
+    pub fn change_log(&self) -> Vec<String> {
+        self.history
+            .done
+            .iter()
+            .map(|change| change.description.clone())
+            .collect()
+    }
+
+    pub fn display_change_log(&self) {
+        for (i, change) in self.change_log().iter().enumerate() {
+            println!("{}. {}", i + 1, change);
+        }
+    }
+
+    /// Writes the change log as a numbered text file.
+    pub fn write_change_log<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let log: String = self
+            .change_log()
+            .iter()
+            .enumerate()
+            .map(|(i, change)| format!("{}. {}\n", i + 1, change))
+            .collect();
+        fs::write(path, log)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::StowSlotBuilder;
+    use crate::test_support::{builder, floor, plan};
+
+    fn one_slot() -> StowSlotBuilder {
+        let aisles: Vec<(u32, i32)> = (1..=6).map(|n| (n, 10)).collect();
+        builder(floor(&[("A", &aisles)]), &[("A", &[1, 2, 3, 4, 5, 6])])
+    }
+
+    #[test]
+    fn undo_reverts_an_edit() {
+        let mut b = one_slot();
+        b.split_slot(0, 2).unwrap();
+        assert_eq!(plan(&b), [vec![1, 2], vec![3, 4, 5, 6]]);
+        let description = b.change_log().pop().unwrap();
+
+        assert_eq!(b.undo(), Some(description));
+        assert_eq!(plan(&b), [vec![1, 2, 3, 4, 5, 6]]);
+        assert!(b.change_log().is_empty());
+        assert!(b.can_redo());
+    }
+
+    #[test]
+    fn redo_applies_an_undone_edit_again() {
+        let mut b = one_slot();
+        b.split_slot(0, 2).unwrap();
+        let log = b.change_log();
+        let undone = b.undo();
+
+        assert_eq!(b.redo(), undone);
+        assert_eq!(plan(&b), [vec![1, 2], vec![3, 4, 5, 6]]);
+        assert_eq!(b.change_log(), log);
+        assert!(!b.can_redo());
+    }
+
+    #[test]
+    fn a_new_change_clears_what_could_be_redone() {
+        let mut b = one_slot();
+        b.split_slot(0, 2).unwrap();
+        b.undo();
+        b.split_slot(0, 4).unwrap();
+
+        assert!(!b.can_redo());
+        assert_eq!(b.redo(), None);
+        assert_eq!(plan(&b), [vec![1, 2, 3, 4], vec![5, 6]]);
+        assert_eq!(b.change_log().len(), 1);
+    }
+
+    #[test]
+    fn an_empty_history_has_nothing_to_undo_or_redo() {
+        let mut b = one_slot();
+        assert_eq!(b.undo(), None);
+        assert_eq!(b.redo(), None);
+        assert_eq!(plan(&b), [vec![1, 2, 3, 4, 5, 6]]);
+    }
+}
@@ -0,0 +1,316 @@
+//! Station geometry: which aisles exist, how they pair up, and how far a stower walks
+//! between them.
+//!
+//! A layout is a TOML file with one table per cluster. `range = [1, 36]` declares every
+//! aisle of the cluster so aisles with no planned volume still exist, `pairs` lists the
+//! aisles that face each other (odd/even neighbours otherwise), and `disabled` takes
+//! aisles out of service. Aisles can be placed on a grid
+//! (`aisles = { 1 = [0.0, 0.0], 2 = [0.0, 1.5] }`, in metres) and walked with Manhattan
+//! distance, or connected explicitly (`links = [{ from = 30, to = 1, distance = 4.0 }]`)
+//! for cross-aisles and clusters that wrap. Aisles the file does not mention are assumed
+//! to sit `aisle_spacing` apart per aisle number.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FloorLayout {
+    /// Walking time per metre; 0 turns the walking penalty off.
+    pub seconds_per_meter: f32,
+    /// Distance between numerically adjacent aisles that have no position or links.
+    pub aisle_spacing: f32,
+    pub clusters: BTreeMap<String, ClusterLayout>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClusterLayout {
+    /// First and last aisle number of the cluster.
+    pub range: Option<[u32; 2]>,
+    /// Aisles that face each other and are stowed together.
+    pub pairs: Vec<[u32; 2]>,
+    /// Aisles that are out of service; nothing is planned into them.
+    pub disabled: Vec<u32>,
+    /// `[x, y]` position of every aisle, keyed by aisle number.
+    pub aisles: BTreeMap<String, [f32; 2]>,
+    /// Walkable connections; when present, distances follow the shortest path along them.
+    pub links: Vec<AisleLink>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AisleLink {
+    pub from: u32,
+    pub to: u32,
+    pub distance: f32,
+}
+
+impl Default for FloorLayout {
+    fn default() -> Self {
+        Self {
+            seconds_per_meter: 0.0,
+            aisle_spacing: 1.0,
+            clusters: BTreeMap::new(),
+        }
+    }
+}
+
+impl FloorLayout {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        Self::from_toml(&fs::read_to_string(path)?)
+    }
+
+    /// Parses and checks a layout. Clusters go under `[clusters.<name>]`; anything else
+    /// is rejected rather than silently ignored.
+    pub fn from_toml(content: &str) -> Result<Self, Box<dyn Error>> {
+        let layout: Self = toml::from_str(content)?;
+        for (cluster, layout) in &layout.clusters {
+            for aisle in layout.aisles.keys() {
+                aisle.parse::<u32>().map_err(|_| {
+                    format!("cluster {}: '{}' is not an aisle number", cluster, aisle)
+                })?;
+            }
+            if let Some([first, last]) = layout.range {
+                if first > last {
+                    return Err(
+                        format!("cluster {}: range {}-{} is empty", cluster, first, last).into(),
+                    );
+                }
+            }
+            let mut paired: Vec<u32> = Vec::new();
+            for &[a, b] in &layout.pairs {
+                if a == b || paired.contains(&a) || paired.contains(&b) {
+                    return Err(format!(
+                        "cluster {}: aisles {} and {} cannot be paired; every aisle faces at most one other",
+                        cluster, a, b
+                    )
+                    .into());
+                }
+                paired.extend([a, b]);
+            }
+            for link in &layout.links {
+                if link.from == link.to || !link.distance.is_finite() || link.distance < 0.0 {
+                    return Err(format!(
+                        "cluster {}: link {}-{} needs two different aisles and a distance of 0 or more",
+                        cluster, link.from, link.to
+                    )
+                    .into());
+                }
+            }
+        }
+        Ok(layout)
+    }
+
+    pub fn cluster(&self, cluster: &str) -> Option<&ClusterLayout> {
+        self.clusters.get(cluster)
+    }
+
+    pub fn is_disabled(&self, cluster: &str, aisle: u32) -> bool {
+        self.cluster(cluster)
+            .is_some_and(|layout| layout.disabled.contains(&aisle))
+    }
+
+    /// Estimated walking distance between two aisles of a cluster.
+    pub fn distance(&self, cluster: &str, from: u32, to: u32) -> f32 {
+        if from == to {
+            return 0.0;
+        }
+        let fallback = self.aisle_spacing * from.abs_diff(to) as f32;
+        let Some(layout) = self.clusters.get(cluster) else {
+            return fallback;
+        };
+        if !layout.links.is_empty() {
+            if let Some(distance) = layout.shortest_path(from, to) {
+                return distance;
+            }
+        }
+        match (layout.position(from), layout.position(to)) {
+            (Some(a), Some(b)) => (a[0] - b[0]).abs() + (a[1] - b[1]).abs(),
+            _ => fallback,
+        }
+    }
+
+    /// Distance walked visiting `aisles` in order.
+    pub fn route_distance(&self, cluster: &str, aisles: &[u32]) -> f32 {
+        aisles
+            .windows(2)
+            .map(|pair| self.distance(cluster, pair[0], pair[1]))
+            .sum()
+    }
+}
+
+impl ClusterLayout {
+    /// Every enabled aisle number the layout declares for the cluster.
+    pub fn declared_aisles(&self) -> Vec<u32> {
+        let mut aisles: Vec<u32> = self
+            .range
+            .map(|[first, last]| (first..=last).collect())
+            .unwrap_or_default();
+        aisles.extend(self.pairs.iter().flatten());
+        aisles.sort_unstable();
+        aisles.dedup();
+        aisles.retain(|aisle| !self.disabled.contains(aisle));
+        aisles
+    }
+
+    fn position(&self, aisle: u32) -> Option<[f32; 2]> {
+        self.aisles.get(&aisle.to_string()).copied()
+    }
+
+    /// Dijkstra over the links, treating every link as walkable both ways.
+    fn shortest_path(&self, from: u32, to: u32) -> Option<f32> {
+        let mut best: HashMap<u32, f32> = HashMap::from([(from, 0.0)]);
+        let mut done: Vec<u32> = Vec::new();
+        loop {
+            let (&aisle, &distance) = best
+                .iter()
+                .filter(|(aisle, _)| !done.contains(aisle))
+                .min_by(|a, b| a.1.total_cmp(b.1))?;
+            if aisle == to {
+                return Some(distance);
+            }
+            done.push(aisle);
+            for link in &self.links {
+                let next = if link.from == aisle {
+                    link.to
+                } else if link.to == aisle {
+                    link.from
+                } else {
+                    continue;
+                };
+                let candidate = distance + link.distance;
+                let entry = best.entry(next).or_insert(f32::INFINITY);
+                if candidate < *entry {
+                    *entry = candidate;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::floor_with_layout;
+
+    fn load_error(toml: &str) -> String {
+        FloorLayout::from_toml(toml).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn rejects_clusters_outside_the_clusters_table() {
+        let error = load_error("[A]\nrange = [1, 4]\n");
+        assert!(error.contains("unknown field `A`"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_empty_ranges() {
+        assert_eq!(
+            load_error("[clusters.A]\nrange = [8, 1]\n"),
+            "cluster A: range 8-1 is empty"
+        );
+    }
+
+    #[test]
+    fn rejects_aisles_paired_twice() {
+        assert_eq!(
+            load_error("[clusters.A]\npairs = [[1, 2], [2, 3]]\n"),
+            "cluster A: aisles 2 and 3 cannot be paired; every aisle faces at most one other"
+        );
+    }
+
+    #[test]
+    fn rejects_bad_links() {
+        for link in [
+            "{ from = 3, to = 3, distance = 1.0 }",
+            "{ from = 1, to = 3, distance = -1.0 }",
+        ] {
+            let error = load_error(&format!("[clusters.A]\nlinks = [{}]\n", link));
+            assert!(error.starts_with("cluster A: link "), "{}", error);
+        }
+    }
+
+    #[test]
+    fn shortest_path_takes_the_cheapest_chain_of_links() {
+        let layout = FloorLayout::from_toml(
+            "[clusters.A]\nlinks = [\n\
+             { from = 1, to = 2, distance = 1.0 },\n\
+             { from = 2, to = 3, distance = 1.0 },\n\
+             { from = 1, to = 3, distance = 5.0 },\n\
+             ]\n",
+        )
+        .unwrap();
+        let cluster = layout.cluster("A").unwrap();
+        assert_eq!(cluster.shortest_path(1, 3), Some(2.0));
+        assert_eq!(cluster.shortest_path(3, 1), Some(2.0));
+        assert_eq!(cluster.shortest_path(1, 9), None);
+    }
+
+    #[test]
+    fn route_distance_sums_each_step() {
+        let layout = FloorLayout::from_toml(
+            "aisle_spacing = 2.0\n\
+             [clusters.A.aisles]\n\
+             1 = [0.0, 0.0]\n\
+             2 = [3.0, 4.0]\n",
+        )
+        .unwrap();
+        // Manhattan between positioned aisles, aisle spacing for the rest.
+        assert_eq!(layout.route_distance("A", &[1, 2]), 7.0);
+        assert_eq!(layout.route_distance("A", &[3, 4, 6]), 6.0);
+        assert_eq!(layout.route_distance("B", &[1, 2, 3]), 4.0);
+        assert_eq!(layout.route_distance("A", &[1]), 0.0);
+    }
+
+    #[test]
+    fn declared_aisles_join_the_range_and_the_pairs() {
+        let layout =
+            FloorLayout::from_toml("[clusters.A]\nrange = [1, 4]\npairs = [[4, 7], [9, 10]]\n")
+                .unwrap();
+        assert_eq!(
+            layout.cluster("A").unwrap().declared_aisles(),
+            [1, 2, 3, 4, 7, 9, 10]
+        );
+    }
+
+    #[test]
+    fn disabled_aisles_are_left_out_of_the_floor() {
+        let layout = "[clusters.A]\nrange = [1, 6]\ndisabled = [3]\n";
+        let floor = floor_with_layout(&[("A", &[(1, 10), (2, 10), (3, 10), (4, 10)])], layout);
+        assert!(floor.context.layout.is_disabled("A", 3));
+        assert!(!floor.context.layout.is_disabled("B", 3));
+
+        let cluster = floor.get_cluster("A").unwrap();
+        let aisles: Vec<u32> = cluster.aisles.iter().map(|a| a.aisle_num).collect();
+        assert_eq!(aisles, [1, 2, 4, 5, 6]);
+        let disabled: Vec<u32> = floor
+            .disabled_bags
+            .iter()
+            .map(|b| b.sort_zone.aisle)
+            .collect();
+        assert_eq!(disabled, [3]);
+    }
+
+    #[test]
+    fn links_route_a_cluster_that_wraps() {
+        let layout = FloorLayout::from_toml(
+            "[clusters.A]\n\
+             aisles = { 1 = [0.0, 0.0], 2 = [0.0, 1.5], 30 = [0.0, 3.0] }\n\
+             links = [\n\
+             { from = 1, to = 2, distance = 1.5 },\n\
+             { from = 29, to = 30, distance = 1.5 },\n\
+             { from = 30, to = 1, distance = 4.0 },\n\
+             ]\n",
+        )
+        .unwrap();
+        // The links win over the positions, and the walk wraps from 30 back to 1.
+        assert_eq!(layout.distance("A", 30, 1), 4.0);
+        assert_eq!(layout.distance("A", 30, 2), 5.5);
+        assert_eq!(layout.route_distance("A", &[29, 30, 1, 2]), 7.0);
+        // Aisle 5 has no link or position, so the walk falls back to the aisle spacing.
+        assert_eq!(layout.distance("A", 2, 5), 3.0);
+    }
+}
@@ -1,2 +1,14 @@
+#![allow(non_snake_case)]
+
+pub mod coverage;
+pub mod edit;
+pub mod export;
+pub mod history;
+pub mod layout;
 pub mod models;
+pub mod partition;
+pub mod report;
+pub mod scorecard;
+#[cfg(test)]
+mod test_support;
 pub mod utils;
@@ -1,43 +1,194 @@
-mod models;
-mod utils;
+#![allow(non_snake_case)]
 
+mod cli;
+mod tui;
+
+use AMZL_Staffing::scorecard::Scorecard;
+use AMZL_Staffing::{export, models, report, utils};
+
+use clap::Parser;
+use cli::{Cli, Command, InputArgs, OutputFormat, PlanArgs};
 use std::cell::RefCell;
-use std::env;
 use std::error::Error;
 use std::io::{self, Write};
-use std::process;
+use std::process::ExitCode;
 use std::rc::Rc;
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        eprintln!("Usage: {} <csv_file_path>", args[0]);
-        println!("Drag CSV file onto executable");
-        wait_for_enter()?;
-        process::exit(1);
+/// An error together with the exit code it should produce.
+struct Failure {
+    code: u8,
+    error: Box<dyn Error>,
+}
+
+impl Failure {
+    fn with_code<E: Into<Box<dyn Error>>>(code: u8) -> impl FnOnce(E) -> Failure {
+        move |error| Failure {
+            code,
+            error: error.into(),
+        }
     }
+}
 
-    let config = utils::Config::load()?;
-    let records = utils::read_csv(&args[1])?;
-    let floor = models::Floor::new(records);
-    print_summary(&floor);
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match &cli.command {
+        Some(Command::Plan(args)) => plan(args),
+        Some(Command::Summary { input, hours }) => summary(input, *hours),
+        // Dragging files onto the executable passes them as the only arguments.
+        None if !cli.files.is_empty() => plan(&PlanArgs::from_files(cli.files.clone())),
+        None => {
+            eprintln!("Usage: AMZL-Staffing [plan|summary] <csv_file_path>...");
+            println!("Drag CSV file onto executable");
+            Err(Failure {
+                code: cli::EXIT_USAGE,
+                error: "no CSV file given".into(),
+            })
+        }
+    };
+
+    let code = match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => {
+            eprintln!("Error: {}", failure.error);
+            ExitCode::from(failure.code)
+        }
+    };
+    // The pause prompt would corrupt machine-readable output.
+    let json =
+        matches!(&cli.command, Some(Command::Plan(args)) if args.format == OutputFormat::Json);
+    if !cli.no_pause && !json {
+        // Nothing useful left to do if stdin is closed.
+        let _ = wait_for_enter();
+    }
+    code
+}
+
+fn plan(args: &PlanArgs) -> Result<(), Failure> {
+    let mut config = utils::Config::load().map_err(Failure::with_code(cli::EXIT_CONFIG))?;
+    args.overrides.apply(&mut config);
+    let text = args.format == OutputFormat::Text;
+
+    let floor = load_floor(&args.input, &config)?;
+    if text {
+        print_summary(&floor);
+    }
 
     let floor_rc = Rc::new(RefCell::new(floor));
     let mut stow_slot_builder = models::StowSlotBuilder::new(Rc::clone(&floor_rc));
 
-    stow_slot_builder.start_algorithm(models::AlgorithmConfig {
-        algorithm: config.algorithm,
-        target_pph: config.target_pph,
-        target_hc: config.target_hc,
-        max_aisle_count: config.max_aisle_count,
-        min_aisle_count: config.min_aisle_count,
-    });
-    print_results(&stow_slot_builder);
+    let mut assignments = match &args.assignments {
+        Some(path) => utils::read_assignments(path).map_err(Failure::with_code(cli::EXIT_INPUT))?,
+        None => Vec::new(),
+    };
+    assignments.extend(args.assign.iter().cloned());
+    for assignment in &assignments {
+        stow_slot_builder
+            .assign(assignment)
+            .map_err(Failure::with_code(cli::EXIT_PLAN))?;
+    }
+
+    stow_slot_builder
+        .start_algorithm(config.algorithm_config())
+        .map_err(Failure::with_code(cli::EXIT_PLAN))?;
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| export::default_export_path(args.input.primary()));
+    if args.interactive {
+        tui::run(&mut stow_slot_builder, &output).map_err(Failure::with_code(cli::EXIT_OUTPUT))?;
+    }
+    let scorecard = Scorecard::new(&stow_slot_builder, config.tolerance);
+    match args.format {
+        OutputFormat::Text => {
+            print_results(&stow_slot_builder);
+            scorecard.display();
+        }
+        OutputFormat::Json => {
+            let mut document = stow_slot_builder.plan_document();
+            document.scorecard = Some(scorecard);
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&document)
+                    .map_err(Failure::with_code(cli::EXIT_OUTPUT))?
+            );
+        }
+    }
 
-    wait_for_enter()?;
+    stow_slot_builder
+        .export_csv(&output)
+        .map_err(|e| format!("{}: {}", output.display(), e))
+        .map_err(Failure::with_code(cli::EXIT_OUTPUT))?;
+    eprintln!("Stow slots written to {}", output.display());
+
+    if let Some(sheets) = &args.sheets {
+        let sheets = sheets
+            .clone()
+            .unwrap_or_else(|| report::default_sheets_path(args.input.primary()));
+        report::write_assignment_sheets(&stow_slot_builder, &sheets)
+            .map_err(|e| format!("{}: {}", sheets.display(), e))
+            .map_err(Failure::with_code(cli::EXIT_OUTPUT))?;
+        eprintln!("Assignment sheets written to {}", sheets.display());
+    }
+
+    if let Some(log) = &args.change_log {
+        stow_slot_builder
+            .write_change_log(log)
+            .map_err(|e| format!("{}: {}", log.display(), e))
+            .map_err(Failure::with_code(cli::EXIT_OUTPUT))?;
+        eprintln!("Change log written to {}", log.display());
+    }
+    Ok(())
+}
+
+fn summary(input: &InputArgs, hours: Option<f32>) -> Result<(), Failure> {
+    let mut config = utils::Config::load().map_err(Failure::with_code(cli::EXIT_CONFIG))?;
+    if let Some(hours) = hours {
+        config.total_hours = hours;
+    }
+    let floor = load_floor(input, &config)?;
+    print_summary(&floor);
     Ok(())
 }
 
+fn load_floor(input: &InputArgs, config: &utils::Config) -> Result<models::Floor, Failure> {
+    let mut options = config
+        .input_options()
+        .map_err(Failure::with_code(cli::EXIT_CONFIG))?;
+    if let Some(sheet) = &input.sheet {
+        options.sheet = sheet.clone();
+    }
+    let records = if input.strict {
+        utils::read_inputs(&input.files, &options, input.by_source)
+    } else {
+        utils::read_inputs_lenient(&input.files, &options, input.by_source).and_then(|report| {
+            report.display_rejected();
+            if let Some(quarantine) = &input.quarantine {
+                report.write_quarantine(quarantine)?;
+                eprintln!("Skipped rows written to {}", quarantine.display());
+            }
+            Ok(report.records)
+        })
+    }
+    .map_err(Failure::with_code(cli::EXIT_INPUT))?;
+    let context = config
+        .planning_context()
+        .map_err(Failure::with_code(cli::EXIT_CONFIG))?;
+    let floor = models::Floor::new(records, context);
+    if !floor.disabled_bags.is_empty() {
+        let packages: i32 = floor
+            .disabled_bags
+            .iter()
+            .map(|b| b.planned_package_count)
+            .sum();
+        eprintln!(
+            "Warning: {} packages are planned into aisles the layout disables and were left out",
+            packages
+        );
+    }
+    Ok(floor)
+}
+
 fn print_summary(floor: &models::Floor) {
     println!(
         "Aisles: {}",
@@ -51,6 +202,15 @@ fn print_results(builder: &models::StowSlotBuilder) {
     builder.display_stow_slots();
     println!("Total Stow Slots: {}", builder.total_stow_slots());
     builder.stow_slots_per_cluster();
+    builder.display_floaters();
+    if let Some(allocation) = &builder.headcount_allocation {
+        allocation.display();
+    }
+    // A plain algorithm run needs no explanation.
+    if builder.change_log().len() > 1 {
+        println!("Changes:");
+        builder.display_change_log();
+    }
 }
 
 fn wait_for_enter() -> io::Result<()> {
@@ -1,29 +1,192 @@
-use crate::utils::Config;
-use once_cell::sync::Lazy;
+use crate::coverage::{self, CoverageReport};
+use crate::history::History;
+use crate::layout::FloorLayout;
+use crate::partition::{self, PartLimits};
+use regex::Regex;
 use serde::de::{self, Deserializer, Visitor};
 use serde::{Deserialize, Serialize};
-use std::cell::RefCell;
+use std::cell::{Ref, RefCell};
+use std::collections::{BTreeMap, HashSet};
 use std::error::Error;
 use std::fmt;
-use std::ops::DerefMut;
 use std::path::Path;
 use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+/// Per-run settings that every PPH calculation depends on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanningContext {
+    pub total_hours: f32,
+    #[serde(default)]
+    pub workload: WorkloadWeights,
+    /// Station geometry for walking estimates; loaded from its own file.
+    #[serde(skip)]
+    pub layout: FloorLayout,
+}
+
+impl Default for PlanningContext {
+    fn default() -> Self {
+        Self {
+            total_hours: 6.5,
+            workload: WorkloadWeights::default(),
+            layout: FloorLayout::default(),
+        }
+    }
+}
+
+impl PlanningContext {
+    /// Package equivalents of walking `distance`, at the layout's walking speed.
+    pub fn walk_cost(&self, distance: f32) -> f32 {
+        if self.workload.seconds_per_package > 0.0 {
+            distance * self.layout.seconds_per_meter / self.workload.seconds_per_package
+        } else {
+            0.0
+        }
+    }
+
+    /// Walking distance of visiting `aisles` in order.
+    pub fn walk_distance(&self, aisles: &[Rc<Aisle>]) -> f32 {
+        let Some(first) = aisles.first() else {
+            return 0.0;
+        };
+        let numbers: Vec<u32> = aisles.iter().map(|a| a.aisle_num).collect();
+        self.layout.route_distance(&first.cluster, &numbers)
+    }
+}
+
+/// How the workload of a sort zone is worked out from its packages and bags.
+///
+/// Workload is measured in package equivalents so it stays comparable with PPH targets:
+/// a bag costs `seconds_per_bag / seconds_per_package` packages. The total is then scaled
+/// by how much harder the level or column is to stow than at waist height. Keys are level
+/// numbers and column letters as they appear in the sort zone; anything not listed weighs
+/// 1.0, and a sort zone's weight is its level weight times its column weight.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkloadWeights {
+    pub seconds_per_package: f32,
+    pub seconds_per_bag: f32,
+    pub levels: BTreeMap<String, f32>,
+    pub columns: BTreeMap<String, f32>,
+}
 
-pub static TOTAL_HOURS: Lazy<f32> = Lazy::new(|| Config::load().unwrap().total_hours);
+impl Default for WorkloadWeights {
+    fn default() -> Self {
+        Self {
+            seconds_per_package: 6.0,
+            seconds_per_bag: 0.0,
+            levels: BTreeMap::new(),
+            columns: BTreeMap::new(),
+        }
+    }
+}
+
+impl WorkloadWeights {
+    /// Package equivalents of one bag.
+    pub fn bag_cost(&self) -> f32 {
+        if self.seconds_per_package > 0.0 {
+            self.seconds_per_bag / self.seconds_per_package
+        } else {
+            0.0
+        }
+    }
 
-#[derive(Default, Serialize, Deserialize, Debug)]
+    pub fn weight(&self, sort_zone: &SortZone) -> f32 {
+        let level = self
+            .levels
+            .get(&sort_zone.level.to_string())
+            .copied()
+            .unwrap_or(1.0);
+        let column = self
+            .columns
+            .iter()
+            .find(|(column, _)| column.eq_ignore_ascii_case(&sort_zone.column))
+            .map(|(_, weight)| *weight)
+            .unwrap_or(1.0);
+        level * column
+    }
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub enum Algorithm {
     TargetHC,
+    Balanced,
+    Proportional,
     #[default]
     TargetPPH,
 }
-#[derive(Serialize, Deserialize)]
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "targethc" => Ok(Algorithm::TargetHC),
+            "balanced" => Ok(Algorithm::Balanced),
+            "proportional" => Ok(Algorithm::Proportional),
+            "targetpph" => Ok(Algorithm::TargetPPH),
+            _ => Err(format!(
+                "unknown algorithm '{}', expected TargetPPH, TargetHC, Balanced or Proportional",
+                s
+            )),
+        }
+    }
+}
+/// What to do where aisle numbers skip, e.g. A-12 followed by A-14.
+#[derive(Default, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum GapPolicy {
+    /// Slots may run across the gap as if the aisles were neighbours.
+    Bridge,
+    /// A gap always ends a slot.
+    #[default]
+    Break,
+    /// Missing aisles are planned as aisles with no volume, so nothing is skipped.
+    ZeroVolume,
+}
+
+impl FromStr for GapPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace(['-', '_'], "").as_str() {
+            "bridge" => Ok(GapPolicy::Bridge),
+            "break" => Ok(GapPolicy::Break),
+            "zerovolume" => Ok(GapPolicy::ZeroVolume),
+            _ => Err(format!(
+                "unknown gap policy '{}', expected Bridge, Break or ZeroVolume",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AlgorithmConfig {
     pub algorithm: Algorithm,
     pub target_pph: i32,
     pub target_hc: i32,
+    /// Most aisle pairs (two facing aisles), not single aisles, per stow slot. TargetPPH
+    /// ignores it.
     pub max_aisle_count: i32,
+    /// Fewest aisle pairs per stow slot.
     pub min_aisle_count: i32,
+    /// Slots at or below this PPH are flagged as floaters.
+    pub floater_pph: f32,
+    /// Floater slots to carve out of every cluster; 0 disables the floater-aware mode.
+    pub floaters_per_cluster: i32,
+    #[serde(default)]
+    pub gap_policy: GapPolicy,
+}
+
+impl AlgorithmConfig {
+    /// `min_aisle_count` and `max_aisle_count` as part limits, at least one pair each.
+    pub fn pair_limits(&self) -> PartLimits {
+        PartLimits::new(
+            self.min_aisle_count.max(1) as usize,
+            self.max_aisle_count.max(1) as usize,
+        )
+    }
 }
 
 impl Default for AlgorithmConfig {
@@ -34,24 +197,97 @@ impl Default for AlgorithmConfig {
             target_hc: 10,
             max_aisle_count: 3,
             min_aisle_count: 2,
+            floater_pph: 150.0,
+            floaters_per_cluster: 0,
+            gap_policy: GapPolicy::default(),
         }
     }
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct SortZone {
-    pub cluster: char,
+    pub cluster: String,
     pub aisle: u32,
     pub level: u32,
-    pub column: char,
+    pub column: String,
+    /// The sort zone as the input spelled it.
+    pub label: String,
 }
 
 impl SortZone {
+    /// The sort zone as the input spelled it, whatever the site's pattern.
     pub fn display(&self) -> String {
-        format!(
-            "{}-{}.{}{}",
-            self.cluster, self.aisle, self.level, self.column
-        )
+        if self.label.is_empty() {
+            format!(
+                "{}-{}.{}{}",
+                self.cluster, self.aisle, self.level, self.column
+            )
+        } else {
+            self.label.clone()
+        }
+    }
+}
+
+/// How a site spells its sort zones, as a regex with named captures.
+///
+/// `cluster` and `aisle` are required; `level` and `column` may be left out for sites
+/// that don't label them.
+#[derive(Debug, Clone)]
+pub struct SortZoneSchema {
+    pattern: Regex,
+}
+
+impl SortZoneSchema {
+    pub const DEFAULT_PATTERN: &'static str =
+        r"^(?P<cluster>[A-Za-z]+)-(?P<aisle>\d+)\.(?P<level>\d+)(?P<column>[A-Za-z]+)$";
+
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let pattern = Regex::new(pattern).map_err(|e| e.to_string())?;
+        for name in ["cluster", "aisle"] {
+            if !pattern.capture_names().any(|n| n == Some(name)) {
+                return Err(format!(
+                    "sort zone pattern is missing the named capture '{}'",
+                    name
+                ));
+            }
+        }
+        Ok(Self { pattern })
+    }
+
+    pub fn parse(&self, value: &str) -> Result<SortZone, String> {
+        let invalid = || {
+            format!(
+                "sort zone '{}' does not match '{}'",
+                value,
+                self.pattern.as_str()
+            )
+        };
+        let captures = self.pattern.captures(value.trim()).ok_or_else(invalid)?;
+        let text = |name: &str| captures.name(name).map(|m| m.as_str()).unwrap_or("");
+        let number = |name: &str| -> Result<u32, String> {
+            match text(name) {
+                "" => Ok(0),
+                digits => digits.parse::<u32>().map_err(|_| invalid()),
+            }
+        };
+
+        let cluster = text("cluster");
+        if cluster.is_empty() {
+            return Err(invalid());
+        }
+        Ok(SortZone {
+            cluster: cluster.to_string(),
+            aisle: number("aisle")?,
+            level: number("level")?,
+            column: text("column").to_string(),
+            label: value.trim().to_string(),
+        })
+    }
+}
+
+impl Default for SortZoneSchema {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_PATTERN).expect("default sort zone pattern is valid")
     }
 }
 
@@ -73,39 +309,10 @@ impl<'de> Deserialize<'de> for SortZone {
             where
                 E: de::Error,
             {
-                let parts: Vec<&str> = value.split('-').collect();
-                if parts.len() != 2 {
-                    return Err(de::Error::custom("expected format 'A-1.1A'"));
-                }
-
-                let cluster = parts[0]
-                    .chars()
-                    .next()
-                    .ok_or_else(|| de::Error::custom("missing cluster"))?;
-                let aisle_level_column: Vec<&str> = parts[1].split('.').collect();
-                if aisle_level_column.len() != 2 {
-                    return Err(de::Error::custom("expected format '1.1A'"));
-                }
-
-                let aisle = aisle_level_column[0]
-                    .parse::<u32>()
-                    .map_err(de::Error::custom)?;
-                let level_column: Vec<char> = aisle_level_column[1].chars().collect();
-                if level_column.len() != 2 {
-                    return Err(de::Error::custom("expected format '1A'"));
-                }
-
-                let level = level_column[0]
-                    .to_digit(10)
-                    .ok_or_else(|| de::Error::custom("invalid level"))?;
-                let column = level_column[1];
-
-                Ok(SortZone {
-                    cluster,
-                    aisle,
-                    level,
-                    column,
-                })
+                // Serde has no way to hand over the configured schema; readers that
+                // know it call `SortZoneSchema::parse` themselves.
+                static DEFAULT: LazyLock<SortZoneSchema> = LazyLock::new(SortZoneSchema::default);
+                DEFAULT.parse(value).map_err(de::Error::custom)
             }
         }
 
@@ -121,17 +328,55 @@ pub struct BagRecord {
     pub planned_bag_count: i32,
     #[serde(rename = "Planned Package Count")]
     pub planned_package_count: i32,
+    /// The input file (wave) the bags came from, when sources are kept apart.
+    #[serde(skip)]
+    pub source: Option<String>,
+    /// Workload weight of the sort zone, from `WorkloadWeights`.
+    #[serde(skip, default = "BagRecord::default_difficulty")]
+    pub difficulty: f32,
+    /// Package equivalents of every bag, from `WorkloadWeights::bag_cost`.
+    #[serde(skip)]
+    pub bag_cost: f32,
+}
+
+impl BagRecord {
+    fn default_difficulty() -> f32 {
+        1.0
+    }
+
+    /// Packages plus the cost of the bags, weighted by how hard the sort zone is to reach.
+    pub fn workload(&self) -> f32 {
+        let bags = self.planned_bag_count as f32 * self.bag_cost;
+        (self.planned_package_count as f32 + bags) * self.difficulty
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct Aisle {
-    pub cluster: char,
+    pub cluster: String,
     pub aisle_num: u32,
     pub bag_records: Vec<BagRecord>,
-    pub locked: bool,
+    /// Filled into a numbering gap by `ZeroVolume`; the aisle is not on the floor. Aisles
+    /// the layout declares are real even when they get no volume.
+    pub phantom: bool,
 }
 
 impl Aisle {
+    /// Adds a record, summing it into an existing one for the same sort zone and source.
+    pub fn add_bag_record(&mut self, bag: BagRecord) {
+        let existing = self
+            .bag_records
+            .iter_mut()
+            .find(|b| b.sort_zone == bag.sort_zone && b.source == bag.source);
+        match existing {
+            Some(record) => {
+                record.planned_bag_count += bag.planned_bag_count;
+                record.planned_package_count += bag.planned_package_count;
+            }
+            None => self.bag_records.push(bag),
+        }
+    }
+
     pub fn total_packages(&self) -> i32 {
         self.bag_records
             .iter()
@@ -139,23 +384,34 @@ impl Aisle {
             .sum()
     }
 
-    pub fn get_aisle_pph(&self) -> f32 {
-        self.total_packages() as f32 / *TOTAL_HOURS
+    pub fn total_bags(&self) -> i32 {
+        self.bag_records.iter().map(|b| b.planned_bag_count).sum()
+    }
+
+    pub fn workload(&self) -> f32 {
+        self.bag_records.iter().map(|b| b.workload()).sum()
+    }
+
+    pub fn get_aisle_pph(&self, total_hours: f32) -> f32 {
+        self.total_packages() as f32 / total_hours
+    }
+
+    pub fn get_aisle_weighted_pph(&self, total_hours: f32) -> f32 {
+        self.workload() / total_hours
     }
 
     pub fn display_aisle(&self) -> String {
         format!("{}-{}", self.cluster, self.aisle_num)
     }
-    pub fn toggle_lock(&mut self) {
-        self.locked = !self.locked;
-    }
 }
 
 #[derive(Debug)]
 pub struct Cluster {
-    pub cluster: char,
+    pub cluster: String,
     pub aisles: Vec<Rc<Aisle>>,
     pub aisle_pairs: Vec<AislePair>,
+    /// Aisles that face each other, from the station layout. Empty means odd/even pairs.
+    pub facing_pairs: Vec<[u32; 2]>,
 }
 
 impl Cluster {
@@ -175,10 +431,43 @@ impl Cluster {
         self.aisles.iter().map(|a| a.total_packages()).sum::<i32>()
     }
 
+    pub fn get_total_bags(&self) -> i32 {
+        self.aisles.iter().map(|a| a.total_bags()).sum()
+    }
+
+    pub fn get_total_workload(&self) -> f32 {
+        self.aisles.iter().map(|a| a.workload()).sum()
+    }
+
     pub fn get_next_aisle(&self, aisle: u32) -> Option<&Rc<Aisle>> {
         self.aisles.iter().find(|a| a.aisle_num == aisle + 1)
     }
 
+    /// Aisle numbers missing between the first and last aisle of the cluster.
+    pub fn gaps(&self) -> Vec<u32> {
+        let (Some(first), Some(last)) = (self.get_first_aisle(), self.get_last_aisle()) else {
+            return Vec::new();
+        };
+        (first.aisle_num..=last.aisle_num)
+            .filter(|&n| self.get_aisle(n).is_none())
+            .collect()
+    }
+
+    /// The aisle before `aisle` in walking order: the numerically previous one, or with
+    /// `Bridge` the closest lower aisle that exists.
+    pub fn get_preceding_aisle(&self, aisle: u32, policy: GapPolicy) -> Option<&Rc<Aisle>> {
+        match policy {
+            GapPolicy::Bridge => self
+                .aisles
+                .iter()
+                .filter(|a| a.aisle_num < aisle)
+                .max_by_key(|a| a.aisle_num),
+            GapPolicy::Break | GapPolicy::ZeroVolume => {
+                aisle.checked_sub(1).and_then(|n| self.get_aisle(n))
+            }
+        }
+    }
+
     pub fn get_previous_aisle(&self, aisle: u32) -> Option<&Rc<Aisle>> {
         self.aisles.iter().find(|a| a.aisle_num == aisle - 1)
     }
@@ -189,33 +478,36 @@ impl Cluster {
         // Sort aisles by aisle number to ensure proper pairing
         self.aisles.sort_by_key(|a| a.aisle_num);
 
-        // Group aisles into pairs (odd with even)
-        for (i, aisle) in self.aisles.iter().enumerate() {
-            // Skip even aisles as starting points (we want odd-even pairs)
-            if aisle.aisle_num % 2 == 0 {
+        // Pair every aisle with the one it faces: from the layout if given, otherwise
+        // odd with the following even. Aisles whose partner is missing stand alone.
+        let facing = |aisle: u32| -> Option<u32> {
+            if self.facing_pairs.is_empty() {
+                return if aisle % 2 == 1 {
+                    Some(aisle + 1)
+                } else {
+                    aisle.checked_sub(1)
+                };
+            }
+            self.facing_pairs.iter().find_map(|&[a, b]| match aisle {
+                _ if aisle == a => Some(b),
+                _ if aisle == b => Some(a),
+                _ => None,
+            })
+        };
+        let mut paired: Vec<u32> = Vec::new();
+        for aisle in &self.aisles {
+            if paired.contains(&aisle.aisle_num) {
                 continue;
             }
-
-            // Find the next aisle (which should be even)
-            if let Some(next_idx) = self
-                .aisles
-                .iter()
-                .position(|a| a.aisle_num == aisle.aisle_num + 1)
-            {
-                // Create a pair with the current (odd) aisle and the next (even) aisle
-                let pair = AislePair {
-                    aisle1: Some(Rc::clone(&self.aisles[i])),
-                    aisle2: Some(Rc::clone(&self.aisles[next_idx])),
-                };
-                self.aisle_pairs.push(pair);
-            } else {
-                // If no matching even aisle, create a pair with just the odd aisle
-                let pair = AislePair {
-                    aisle1: Some(Rc::clone(&self.aisles[i])),
-                    aisle2: None,
-                };
-                self.aisle_pairs.push(pair);
+            let partner = facing(aisle.aisle_num).and_then(|n| self.get_aisle(n));
+            paired.push(aisle.aisle_num);
+            if let Some(partner) = partner {
+                paired.push(partner.aisle_num);
             }
+            self.aisle_pairs.push(AislePair {
+                aisle1: Some(Rc::clone(aisle)),
+                aisle2: partner.map(Rc::clone),
+            });
         }
     }
     pub fn aisle_pairs_len(&mut self) -> usize {
@@ -238,7 +530,7 @@ impl Cluster {
     }
 
     pub fn get_pair_from_aisle(&self, aisle: &Rc<Aisle>) -> Option<(usize, &AislePair)> {
-        self.aisle_pairs.iter().enumerate().find(|(i, p)| {
+        self.aisle_pairs.iter().enumerate().find(|(_, p)| {
             p.aisle1
                 .as_ref()
                 .is_some_and(|a| a.aisle_num == aisle.aisle_num)
@@ -299,16 +591,26 @@ impl Cluster {
 #[derive(Debug)]
 pub struct Floor {
     pub clusters: Vec<Cluster>,
+    pub context: PlanningContext,
+    /// Planned bags on aisles the layout disables; they are left out of every plan.
+    pub disabled_bags: Vec<BagRecord>,
 }
 
 impl Floor {
-    pub fn new(bags: Vec<BagRecord>) -> Self {
+    pub fn new(bags: Vec<BagRecord>, context: PlanningContext) -> Self {
         let mut clusters: Vec<Cluster> = Vec::new();
-        for bag in bags {
-            let cluster_char = bag.sort_zone.cluster;
+        let mut disabled_bags = Vec::new();
+        for mut bag in bags {
+            bag.difficulty = context.workload.weight(&bag.sort_zone);
+            bag.bag_cost = context.workload.bag_cost();
+            let cluster_name = bag.sort_zone.cluster.clone();
             let aisle_number = bag.sort_zone.aisle;
+            if context.layout.is_disabled(&cluster_name, aisle_number) {
+                disabled_bags.push(bag);
+                continue;
+            }
 
-            let cluster = clusters.iter_mut().find(|c| c.cluster == cluster_char);
+            let cluster = clusters.iter_mut().find(|c| c.cluster == cluster_name);
             if let Some(cluster) = cluster {
                 let aisle = cluster
                     .aisles
@@ -319,46 +621,78 @@ impl Floor {
                     let aisle_clone = Rc::clone(aisle);
                     // Check if we can get a mutable reference
                     if let Some(aisle_mut) = Rc::get_mut(aisle) {
-                        aisle_mut.bag_records.push(bag);
+                        aisle_mut.add_bag_record(bag);
                     } else {
                         // If we can't get a mutable reference, create a new Aisle with the updated bag_records
-                        let mut new_bag_records = aisle_clone.bag_records.clone();
-                        new_bag_records.push(bag);
-                        *aisle = Rc::new(Aisle {
-                            cluster: cluster_char,
+                        let mut new_aisle = Aisle {
+                            cluster: cluster_name.clone(),
                             aisle_num: aisle_number,
-                            bag_records: new_bag_records,
-                            ..Default::default()
-                        });
+                            bag_records: aisle_clone.bag_records.clone(),
+                            phantom: false,
+                        };
+                        new_aisle.add_bag_record(bag);
+                        *aisle = Rc::new(new_aisle);
                     }
                 } else {
                     cluster.aisles.push(Rc::new(Aisle {
-                        cluster: cluster_char,
+                        cluster: cluster_name.clone(),
                         aisle_num: aisle_number,
                         bag_records: vec![bag],
-                        ..Default::default()
+                        phantom: false,
                     }));
                 }
             } else {
                 clusters.push(Cluster {
-                    cluster: cluster_char,
+                    cluster: cluster_name.clone(),
                     aisles: vec![Rc::new(Aisle {
-                        cluster: cluster_char,
+                        cluster: cluster_name.clone(),
                         aisle_num: aisle_number,
                         bag_records: vec![bag],
-                        ..Default::default()
+                        phantom: false,
                     })],
                     aisle_pairs: Vec::new(),
+                    facing_pairs: Vec::new(),
                 });
             }
         }
 
+        // Aisles the layout declares exist even on days they get no volume.
+        for (cluster_name, layout) in &context.layout.clusters {
+            let index = match clusters.iter().position(|c| &c.cluster == cluster_name) {
+                Some(index) => index,
+                None => {
+                    clusters.push(Cluster {
+                        cluster: cluster_name.clone(),
+                        aisles: Vec::new(),
+                        aisle_pairs: Vec::new(),
+                        facing_pairs: Vec::new(),
+                    });
+                    clusters.len() - 1
+                }
+            };
+            let cluster = &mut clusters[index];
+            for aisle_num in layout.declared_aisles() {
+                if cluster.get_aisle(aisle_num).is_none() {
+                    cluster.aisles.push(Rc::new(Aisle {
+                        cluster: cluster_name.clone(),
+                        aisle_num,
+                        ..Default::default()
+                    }));
+                }
+            }
+            cluster.facing_pairs = layout.pairs.clone();
+        }
+
         // Sort aisles by aisle number
         for cluster in &mut clusters {
             cluster.aisles.sort_by_key(|a| a.aisle_num);
         }
 
-        let mut floor = Self { clusters };
+        let mut floor = Self {
+            clusters,
+            context,
+            disabled_bags,
+        };
         floor.generate_aisle_pairs();
         floor
     }
@@ -368,20 +702,24 @@ impl Floor {
             .iter()
             .map(|c| c.aisles.iter().map(|a| a.total_packages()).sum::<i32>())
             .sum::<i32>() as f32
-            / *TOTAL_HOURS
+            / self.context.total_hours
     }
 
-    pub fn get_aisle_in_cluster(&self, cluster: char, aisle: u32) -> Option<&Rc<Aisle>> {
+    pub fn get_aisle_in_cluster(&self, cluster: &str, aisle: u32) -> Option<&Rc<Aisle>> {
         self.clusters
             .iter()
             .find(|c| c.cluster == cluster)
             .and_then(|c| c.aisles.iter().find(|a| a.aisle_num == aisle))
     }
 
-    pub fn get_cluster(&self, cluster: char) -> Option<&Cluster> {
+    pub fn get_cluster(&self, cluster: &str) -> Option<&Cluster> {
         self.clusters.iter().find(|c| c.cluster == cluster)
     }
 
+    pub fn get_total_workload(&self) -> f32 {
+        self.clusters.iter().map(|c| c.get_total_workload()).sum()
+    }
+
     pub fn get_total_packages(&self) -> i32 {
         self.clusters
             .iter()
@@ -389,12 +727,40 @@ impl Floor {
             .sum::<i32>()
     }
 
-    pub fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
-        let records = crate::utils::read_csv(path.as_ref().to_str().unwrap())?;
-        Ok(Self::new(records))
+    pub fn from_csv<P: AsRef<Path>>(
+        path: P,
+        context: PlanningContext,
+    ) -> Result<Self, Box<dyn Error>> {
+        let records = crate::utils::read_csv(
+            path.as_ref().to_str().unwrap(),
+            &crate::utils::InputOptions::default(),
+        )?;
+        Ok(Self::new(records, context))
+    }
+
+    /// Builds a floor from a CSV or .xlsx export, detected by extension or content.
+    pub fn from_file<P: AsRef<Path>>(
+        path: P,
+        context: PlanningContext,
+        options: &crate::utils::InputOptions,
+    ) -> Result<Self, Box<dyn Error>> {
+        Self::from_files(&[path], context, options, false)
+    }
+
+    /// Merges several exports (e.g. one per wave) into one floor, summing the bags of
+    /// every sort zone. With `by_source` each record keeps the name of its file so
+    /// slots can still report PPH per wave.
+    pub fn from_files<P: AsRef<Path>>(
+        paths: &[P],
+        context: PlanningContext,
+        options: &crate::utils::InputOptions,
+        by_source: bool,
+    ) -> Result<Self, Box<dyn Error>> {
+        let records = crate::utils::read_inputs(paths, options, by_source)?;
+        Ok(Self::new(records, context))
     }
 
-    pub fn cluster(&self, cluster: char) -> Option<&Cluster> {
+    pub fn cluster(&self, cluster: &str) -> Option<&Cluster> {
         self.clusters.iter().find(|c| c.cluster == cluster)
     }
 
@@ -411,6 +777,30 @@ impl Floor {
         }
     }
 
+    /// A copy of the cluster with an empty aisle in every gap, except aisles the layout
+    /// disables, and its pairs regenerated. The floor itself is left as it is.
+    pub fn with_gaps_filled(&self, cluster: &Cluster) -> Cluster {
+        let mut filled = Cluster {
+            cluster: cluster.cluster.clone(),
+            aisles: cluster.aisles.clone(),
+            aisle_pairs: Vec::new(),
+            facing_pairs: cluster.facing_pairs.clone(),
+        };
+        for aisle_num in cluster.gaps() {
+            if self.context.layout.is_disabled(&cluster.cluster, aisle_num) {
+                continue;
+            }
+            filled.aisles.push(Rc::new(Aisle {
+                cluster: cluster.cluster.clone(),
+                aisle_num,
+                phantom: true,
+                ..Default::default()
+            }));
+        }
+        filled.generate_aisle_pairs();
+        filled
+    }
+
     pub fn get_all_aisle_pairs(&self) -> Vec<&AislePair> {
         self.clusters
             .iter()
@@ -430,21 +820,33 @@ impl Floor {
 
 #[derive(Debug, Clone)]
 pub struct StowSlot {
-    pub cluster: char,
+    pub cluster: String,
     pub aisles: Vec<Rc<Aisle>>,
     pub is_floater: bool,
     pub pph: f32,
+    /// Workload per hour; what the algorithms balance. Equals `pph` with unit weights.
+    pub weighted_pph: f32,
+    /// Estimated walk through the slot's aisles, when a layout with a walking speed is set.
+    pub walk_distance: f32,
+    /// Locked slots are kept as they are by every algorithm; see `StowSlotBuilder::assign`.
     pub locked: bool,
+    /// Who the slot is pre-assigned to.
+    pub stower: Option<String>,
+    pub total_hours: f32,
 }
 
 impl StowSlot {
-    pub fn new(cluster: char, aisles: Vec<Rc<Aisle>>) -> Self {
+    pub fn new(cluster: String, aisles: Vec<Rc<Aisle>>, total_hours: f32) -> Self {
         let mut obj = Self {
             cluster,
             aisles,
             is_floater: false,
             pph: 0.0,
+            weighted_pph: 0.0,
+            walk_distance: 0.0,
             locked: false,
+            stower: None,
+            total_hours,
         };
         obj.update_pph();
         obj
@@ -460,10 +862,22 @@ impl StowSlot {
         self.update_pph();
     }
 
-    fn update_pph(&mut self) {
+    fn remove_first_aisles(&mut self, count: usize) -> Vec<Rc<Aisle>> {
+        let aisles = self.aisles.drain(..count).collect();
+        self.update_pph();
+        aisles
+    }
+
+    fn remove_last_aisles(&mut self, count: usize) -> Vec<Rc<Aisle>> {
+        let aisles = self.aisles.split_off(self.aisles.len() - count);
+        self.update_pph();
+        aisles
+    }
+
+    pub(crate) fn update_pph(&mut self) {
         self.pph =
-            self.aisles.iter().map(|a| a.total_packages()).sum::<i32>() as f32 / *TOTAL_HOURS;
-        self.is_floater = self.pph <= 150.0;
+            self.aisles.iter().map(|a| a.total_packages()).sum::<i32>() as f32 / self.total_hours;
+        self.weighted_pph = self.workload() / self.total_hours;
     }
 
     pub fn display_aisles(&self) {
@@ -473,13 +887,105 @@ impl StowSlot {
     }
 
     pub fn display_aisle_range(&self) {
+        let mut weighted = if self.weighted_pph as i32 != self.pph as i32 {
+            format!(" (weighted {})", self.weighted_pph as i32)
+        } else {
+            String::new()
+        };
+        if self.walk_distance > 0.0 {
+            weighted.push_str(&format!(", walk {:.0}m", self.walk_distance));
+        }
+        let gaps = self.gap_labels();
+        if !gaps.is_empty() {
+            weighted.push_str(&format!(", spans gap at {}", gaps.join(", ")));
+        }
+        let stower = match &self.stower {
+            Some(stower) => format!(" [{}]", stower),
+            None => String::new(),
+        };
         println!(
-            "{} - {}: {} PPH, is floater: {}",
-            self.aisles.first().unwrap().display_aisle(),
-            self.aisles.last().unwrap().display_aisle(),
+            "{}{}: {} PPH{}, {} packages, {} bags, is floater: {}",
+            self.display_range(),
+            stower,
             self.pph as i32,
+            weighted,
+            self.total_packages(),
+            self.total_bags(),
             self.is_floater
         );
+        for (source, pph) in self.pph_by_source() {
+            println!("    {}: {} PPH", source, pph as i32);
+        }
+    }
+
+    pub fn display_range(&self) -> String {
+        let first = self.floor_aisles().next().or(self.aisles.first());
+        let last = self.floor_aisles().last().or(self.aisles.last());
+        format!(
+            "{} - {}",
+            first.unwrap().display_aisle(),
+            last.unwrap().display_aisle()
+        )
+    }
+
+    /// The slot's aisles that are on the floor, leaving out phantom gap fillers.
+    pub fn floor_aisles(&self) -> impl Iterator<Item = &Rc<Aisle>> {
+        self.aisles.iter().filter(|a| !a.phantom)
+    }
+
+    pub fn total_packages(&self) -> i32 {
+        self.aisles.iter().map(|a| a.total_packages()).sum()
+    }
+
+    pub fn total_bags(&self) -> i32 {
+        self.aisles.iter().map(|a| a.total_bags()).sum()
+    }
+
+    pub fn workload(&self) -> f32 {
+        self.aisles.iter().map(|a| a.workload()).sum()
+    }
+
+    /// Packages per tagged source, sorted by source name.
+    pub fn packages_by_source(&self) -> Vec<(String, i32)> {
+        let mut sources: Vec<(String, i32)> = Vec::new();
+        let records = self.aisles.iter().flat_map(|a| a.bag_records.iter());
+        for record in records {
+            let Some(source) = &record.source else {
+                continue;
+            };
+            match sources.iter_mut().find(|(s, _)| s == source) {
+                Some((_, packages)) => *packages += record.planned_package_count,
+                None => sources.push((source.clone(), record.planned_package_count)),
+            }
+        }
+        sources.sort_by(|a, b| a.0.cmp(&b.0));
+        sources
+    }
+
+    /// The share of this slot's PPH that comes from each source over the same hours.
+    pub fn pph_by_source(&self) -> Vec<(String, f32)> {
+        self.packages_by_source()
+            .into_iter()
+            .map(|(source, packages)| (source, packages as f32 / self.total_hours))
+            .collect()
+    }
+
+    /// Aisle numbers between the slot's first and last floor aisle that it does not
+    /// contain. A phantom aisle counts as missing, since nobody stows there.
+    pub fn gaps(&self) -> Vec<u32> {
+        let numbers: Vec<u32> = self.floor_aisles().map(|a| a.aisle_num).collect();
+        let (Some(&first), Some(&last)) = (numbers.iter().min(), numbers.iter().max()) else {
+            return Vec::new();
+        };
+        (first..=last).filter(|n| !numbers.contains(n)).collect()
+    }
+
+    /// The gaps as aisle names, e.g. `A-14`.
+    pub fn gap_labels(&self) -> Vec<String> {
+        self.gaps()
+            .iter()
+            .map(|n| format!("{}-{}", self.cluster, n))
+            .collect()
     }
 
     pub fn is_consecutive(&self) -> bool {
@@ -488,14 +994,6 @@ impl StowSlot {
             .zip(self.aisles.iter().skip(1))
             .all(|(a, b)| a.aisle_num + 1 == b.aisle_num)
     }
-    pub fn toggle_lock(&mut self) {
-        self.locked = !self.locked;
-        for aisle in &mut self.aisles {
-            if let Some(aisle_mut) = Rc::get_mut(aisle) {
-                aisle_mut.locked = self.locked;
-            }
-        }
-    }
 }
 
 #[derive(Debug, Clone)]
@@ -520,11 +1018,22 @@ impl AislePair {
         total
     }
 
+    pub fn workload(&self) -> f32 {
+        [&self.aisle1, &self.aisle2]
+            .into_iter()
+            .flatten()
+            .map(|aisle| aisle.workload())
+            .sum()
+    }
+
     pub fn get_aisles(&self) -> Vec<Rc<Aisle>> {
         let mut aisles = Vec::new();
         if let Some(aisle) = &self.aisle1 {
             aisles.push(Rc::clone(aisle));
         }
+        if let Some(aisle) = &self.aisle2 {
+            aisles.push(Rc::clone(aisle));
+        }
         aisles
     }
 
@@ -538,10 +1047,146 @@ impl AislePair {
     }
 }
 
+/// How many stowers a cluster received from a floor-wide headcount, and why.
+#[derive(Debug, Clone)]
+pub struct ClusterHeadcount {
+    pub cluster: String,
+    pub total_packages: i32,
+    pub total_bags: i32,
+    /// Package equivalents of the bags and packages; the quota is proportional to this.
+    pub workload: f32,
+    /// Exact proportional share of the floor headcount before rounding.
+    pub quota: f32,
+    pub headcount: usize,
+    /// Spread of weighted PPH between the cluster's slots.
+    pub min_pph: f32,
+    pub max_pph: f32,
+    pub mean_pph: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct HeadcountAllocation {
+    pub total_headcount: usize,
+    pub clusters: Vec<ClusterHeadcount>,
+}
+
+impl HeadcountAllocation {
+    /// Fills in the weighted PPH spread of every cluster from the finished stow slots.
+    pub fn update_spread(&mut self, stow_slots: &[StowSlot]) {
+        for cluster in &mut self.clusters {
+            let pph: Vec<f32> = stow_slots
+                .iter()
+                .filter(|s| s.cluster == cluster.cluster)
+                .map(|s| s.weighted_pph)
+                .collect();
+            if pph.is_empty() {
+                continue;
+            }
+            cluster.min_pph = pph.iter().cloned().fold(f32::INFINITY, f32::min);
+            cluster.max_pph = pph.iter().cloned().fold(0.0, f32::max);
+            cluster.mean_pph = pph.iter().sum::<f32>() / pph.len() as f32;
+        }
+    }
+
+    pub fn display(&self) {
+        println!("Headcount allocation ({} stowers):", self.total_headcount);
+        for c in &self.clusters {
+            println!(
+                "cluster {}: {} packages, {} bags, workload {}, quota {:.2}, headcount {}, weighted PPH {} - {} (mean {})",
+                c.cluster,
+                c.total_packages,
+                c.total_bags,
+                c.workload as i32,
+                c.quota,
+                c.headcount,
+                c.min_pph as i32,
+                c.max_pph as i32,
+                c.mean_pph as i32
+            );
+        }
+    }
+}
+
+/// A range of aisles reserved for a named stower, e.g. a trainer or special handling.
+///
+/// Written as `Name=A-7..A-10`, or `Name=A-7` for an aisle with no facing partner. The
+/// range has to hold whole aisle pairs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assignment {
+    pub stower: String,
+    pub cluster: String,
+    pub first_aisle: u32,
+    pub last_aisle: u32,
+}
+
+impl Assignment {
+    /// Parses an aisle range such as `A-7..A-10` or `A-7`.
+    pub fn new(stower: &str, aisles: &str) -> Result<Self, String> {
+        let stower = stower.trim();
+        if stower.is_empty() {
+            return Err(format!("assignment of {} has no stower name", aisles));
+        }
+        let aisle = |name: &str| -> Result<(String, u32), String> {
+            let (cluster, number) = name
+                .trim()
+                .rsplit_once('-')
+                .ok_or_else(|| format!("'{}' is not an aisle like A-7", name.trim()))?;
+            let number = number
+                .parse::<u32>()
+                .map_err(|_| format!("'{}' is not an aisle like A-7", name.trim()))?;
+            Ok((cluster.to_string(), number))
+        };
+        let (first, last) = match aisles.split_once("..") {
+            Some((first, last)) => (aisle(first)?, aisle(last)?),
+            None => (aisle(aisles)?, aisle(aisles)?),
+        };
+        if first.0 != last.0 {
+            return Err(format!(
+                "{} spans clusters {} and {}",
+                aisles, first.0, last.0
+            ));
+        }
+        Ok(Self {
+            stower: stower.to_string(),
+            cluster: first.0,
+            first_aisle: first.1.min(last.1),
+            last_aisle: first.1.max(last.1),
+        })
+    }
+    /// `A-7..A-10`, or `A-7` for a single aisle.
+    pub fn display_aisles(&self) -> String {
+        if self.first_aisle == self.last_aisle {
+            format!("{}-{}", self.cluster, self.first_aisle)
+        } else {
+            format!(
+                "{}-{}..{}-{}",
+                self.cluster, self.first_aisle, self.cluster, self.last_aisle
+            )
+        }
+    }
+}
+
+impl FromStr for Assignment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (stower, aisles) = s
+            .split_once('=')
+            .ok_or_else(|| format!("'{}' should look like Name=A-7..A-10", s))?;
+        Self::new(stower, aisles)
+    }
+}
+
 #[derive(Debug)]
 pub struct StowSlotBuilder {
     floor: Rc<RefCell<Floor>>,
     pub stow_slots: Vec<StowSlot>,
+    pub headcount_allocation: Option<HeadcountAllocation>,
+    /// The settings of the last `start_algorithm` run.
+    pub algorithm_config: Option<AlgorithmConfig>,
+    /// Aisles in locked slots, as `(cluster, aisle number)`.
+    pub(crate) locked_aisles: HashSet<(String, u32)>,
+    pub(crate) history: History,
 }
 
 impl StowSlotBuilder {
@@ -549,7 +1194,147 @@ impl StowSlotBuilder {
         Self {
             floor,
             stow_slots: Vec::new(),
+            headcount_allocation: None,
+            algorithm_config: None,
+            locked_aisles: HashSet::new(),
+            history: History::default(),
+        }
+    }
+
+    pub fn floor(&self) -> Ref<'_, Floor> {
+        self.floor.borrow()
+    }
+
+    /// Checks that every aisle on the floor is in exactly one slot.
+    pub fn validate_coverage(&self) -> CoverageReport {
+        coverage::validate_coverage(&self.floor(), &self.stow_slots)
+    }
+
+    pub fn is_locked(&self, aisle: &Aisle) -> bool {
+        self.locked_aisles
+            .contains(&(aisle.cluster.clone(), aisle.aisle_num))
+    }
+
+    /// Locks or unlocks the slot at `index`, together with its aisles.
+    pub fn set_slot_locked(&mut self, index: usize, locked: bool) -> Result<(), String> {
+        let before = self.snapshot();
+        self.lock_slot(index, locked)?;
+        let slot = &self.stow_slots[index];
+        let verb = if locked { "Locked" } else { "Unlocked" };
+        self.record(format!("{} {}", verb, slot.display_range()), before);
+        Ok(())
+    }
+
+    fn lock_slot(&mut self, index: usize, locked: bool) -> Result<(), String> {
+        let slot = self
+            .stow_slots
+            .get_mut(index)
+            .ok_or_else(|| format!("there is no stow slot {}", index + 1))?;
+        slot.locked = locked;
+        for aisle in &slot.aisles {
+            let key = (aisle.cluster.clone(), aisle.aisle_num);
+            if locked {
+                self.locked_aisles.insert(key);
+            } else {
+                self.locked_aisles.remove(&key);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn toggle_slot_lock(&mut self, index: usize) -> Result<(), String> {
+        let locked = self
+            .stow_slots
+            .get(index)
+            .map(|s| s.locked)
+            .ok_or_else(|| format!("there is no stow slot {}", index + 1))?;
+        self.set_slot_locked(index, !locked)
+    }
+
+    /// Reserves the assignment's aisles as a locked slot for its stower. Every algorithm
+    /// keeps the slot and plans the remaining aisles around it. The range has to hold
+    /// whole aisle pairs, since one stower works both facing aisles.
+    pub fn assign(&mut self, assignment: &Assignment) -> Result<(), String> {
+        self.ensure_aisle_pairs();
+        let range = assignment.first_aisle..=assignment.last_aisle;
+        let aisles: Vec<Rc<Aisle>> = {
+            let floor = self.floor.borrow();
+            let cluster = floor.get_cluster(&assignment.cluster).ok_or_else(|| {
+                format!("{}: no cluster {}", assignment.stower, assignment.cluster)
+            })?;
+            let aisles: Vec<Rc<Aisle>> = cluster
+                .aisles
+                .iter()
+                .filter(|a| range.contains(&a.aisle_num))
+                .cloned()
+                .collect();
+            let split: Vec<&AislePair> = cluster
+                .aisle_pairs
+                .iter()
+                .filter(|pair| {
+                    let inside = pair
+                        .get_aisles()
+                        .iter()
+                        .filter(|a| range.contains(&a.aisle_num))
+                        .count();
+                    inside > 0 && inside < pair.get_aisles().len()
+                })
+                .collect();
+            if !split.is_empty() {
+                let numbers = aisles
+                    .iter()
+                    .chain(
+                        split
+                            .iter()
+                            .flat_map(|pair| pair.aisle1.iter().chain(&pair.aisle2)),
+                    )
+                    .map(|a| a.aisle_num);
+                let (first, last) = (numbers.clone().min(), numbers.max());
+                return Err(format!(
+                    "{}: {} splits the facing aisles {}; assign whole pairs, e.g. {}-{}..{}-{}",
+                    assignment.stower,
+                    assignment.display_aisles(),
+                    split
+                        .iter()
+                        .map(|p| p.display())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    assignment.cluster,
+                    first.unwrap_or_default(),
+                    assignment.cluster,
+                    last.unwrap_or_default()
+                ));
+            }
+            aisles
+        };
+        if aisles.is_empty() {
+            return Err(format!(
+                "{}: {} is not on the floor",
+                assignment.stower,
+                assignment.display_aisles()
+            ));
+        }
+        if let Some(taken) = aisles.iter().find(|a| self.is_locked(a)) {
+            return Err(format!(
+                "{}: {} is already assigned",
+                assignment.stower,
+                taken.display_aisle()
+            ));
         }
+        let mut slot = StowSlot::new(assignment.cluster.clone(), aisles, self.total_hours());
+        slot.stower = Some(assignment.stower.clone());
+        let before = self.snapshot();
+        self.stow_slots.push(slot);
+        self.lock_slot(self.stow_slots.len() - 1, true)?;
+        self.record(
+            format!(
+                "Assigned {} to {}",
+                assignment.display_aisles(),
+                assignment.stower
+            ),
+            before,
+        );
+        Ok(())
     }
 
     pub fn get_stow_slot_from_aisle(&mut self, aisle: &Rc<Aisle>) -> Option<&mut StowSlot> {
@@ -570,53 +1355,6 @@ impl StowSlotBuilder {
         self.stow_slots.len() as i32
     }
 
-    pub fn get_lowest_pph(
-        &self,
-        cluster: &Cluster,
-        aisle_pair_range: usize,
-        max_aisle_count: usize,
-    ) -> Vec<(StowSlot, f32)> {
-        let mut stow_slots = Vec::new();
-        // iterate through the aisle pairs and get the next n aisles and calculate the pph, return the lowest pph range.
-        for i in 0..cluster.aisle_pairs.len() {
-            if i + aisle_pair_range > cluster.aisle_pairs.len() {
-                break;
-            }
-            if cluster.aisle_pairs[i..i + aisle_pair_range]
-                .iter()
-                .any(|pair| pair.aisle1.as_ref().is_some_and(|aisle| aisle.locked))
-            {
-                continue;
-            }
-            let pph = cluster.aisle_pairs[i..i + aisle_pair_range]
-                .iter()
-                .map(|pair| {
-                    let aisles = cluster.get_aisles_from_pair(pair);
-                    aisles
-                        .iter()
-                        .map(|a| a.total_packages() as f32 / *TOTAL_HOURS)
-                        .sum::<f32>()
-                })
-                .sum::<f32>();
-            stow_slots.push((
-                StowSlot::new(
-                    cluster.cluster,
-                    cluster.aisle_pairs[i..i + aisle_pair_range]
-                        .iter()
-                        .flat_map(|pair| cluster.get_aisles_from_pair(pair))
-                        .collect(),
-                ),
-                pph,
-            ));
-        }
-        stow_slots.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
-
-        // Take only the first five elements (or fewer if there aren't five)
-        // let count = std::cmp::min(max_aisle_count, stow_slots.len());
-        // stow_slots.into_iter().take(count).collect()
-        stow_slots
-    }
-
     pub fn stow_slots_per_cluster(&self) {
         // Create a local copy of the data we need to avoid borrowing issues
         let clusters: Vec<_> = self
@@ -624,55 +1362,34 @@ impl StowSlotBuilder {
             .borrow()
             .clusters
             .iter()
-            .map(|c| c.cluster)
+            .map(|c| c.cluster.clone())
             .collect();
 
-        for cluster_char in clusters {
+        for cluster_name in clusters {
             println!(
                 "stow slots in cluster {}: {}",
-                cluster_char,
+                cluster_name,
                 self.stow_slots
                     .iter()
-                    .filter(|s| s.cluster == cluster_char)
+                    .filter(|s| s.cluster == cluster_name)
                     .count()
             );
         }
     }
 
-    pub fn fill_stow_slots_around_locked(&mut self, max_aisle_count: usize) {
-        let mut new_stow_slots: Vec<StowSlot> = Vec::new();
-        for cluster in &mut self.floor.borrow_mut().clusters {
-            let mut count = 0;
-            let mut current_slot: StowSlot = StowSlot::new(cluster.cluster, Vec::new());
-            for aisle_pair in &mut cluster.aisle_pairs {
-                let aisles = aisle_pair.get_aisles();
-                // if the count is greater than the max_aisle_count or the aisles have a locked aisle, create a new stow slot and return
-                if count >= max_aisle_count || aisles.iter().any(|a| a.locked) {
-                    new_stow_slots.push(current_slot);
-                    current_slot = StowSlot::new(cluster.cluster, Vec::new());
-                    count = 0;
-                } else {
-                    count += 1;
-                    current_slot.add_aisles(aisles);
-                }
-            }
-            new_stow_slots.push(current_slot);
-        }
-        self.stow_slots = new_stow_slots;
-    }
-
     pub fn add_stow_slot(
         &mut self,
         mut stow_slot: StowSlot,
         is_locked: bool,
     ) -> Result<(), String> {
-        if stow_slot.aisles.iter().any(|a| a.locked) {
+        if stow_slot.aisles.iter().any(|a| self.is_locked(a)) {
             return Err("Stow slot contains locked aisles".to_string());
         }
+        let locked = |a: &Rc<Aisle>| self.is_locked(a);
 
         // TODO: Check if the current stow slot is surrounded by other stow slots with only 1 aisle pair, if so this stow slot should not be made. from self.stow_slots.
         let floor = self.floor.borrow();
-        let cluster = floor.get_cluster(stow_slot.cluster).unwrap();
+        let cluster = floor.get_cluster(&stow_slot.cluster).unwrap();
         stow_slot.aisles.sort_by_key(|a| a.aisle_num);
         let (i1, _pair1) = cluster
             .get_pair_from_aisle(stow_slot.aisles.first().unwrap())
@@ -684,19 +1401,22 @@ impl StowSlotBuilder {
         let mut aisle_pairs = cluster.aisle_pairs.clone();
         aisle_pairs.sort_by_key(|p| p.aisle1.as_ref().unwrap().aisle_num);
 
-        match (aisle_pairs.get(i1 - 2), aisle_pairs.get(i1 - 1)) {
+        match (
+            i1.checked_sub(2).and_then(|i| aisle_pairs.get(i)),
+            i1.checked_sub(1).and_then(|i| aisle_pairs.get(i)),
+        ) {
             (Some(pair2), Some(pair1)) => {
                 // if pair2 has no aisles or has a locked aisle and pair1 has an aisle that is not locked, return true.
                 if pair2.get_aisles().is_empty()
-                    || pair2.get_aisles().iter().any(|a| a.locked)
-                        && pair1.get_aisles().iter().any(|a| !a.locked)
+                    || pair2.get_aisles().iter().any(locked)
+                        && pair1.get_aisles().iter().any(|a| !locked(a))
                 {
                     return Err("Stow slot is not allowed to be created".to_string());
                 }
             }
             (None, Some(pair1)) => {
                 // if pair2 doesn't exist and pair1 has no aisles or pair1 doesn't have a locked aisle, return true.
-                if pair1.get_aisles().is_empty() || pair1.get_aisles().iter().any(|a| !a.locked) {
+                if pair1.get_aisles().is_empty() || pair1.get_aisles().iter().any(|a| !locked(a)) {
                     return Err("Stow slot is not allowed to be created".to_string());
                 }
             }
@@ -708,15 +1428,15 @@ impl StowSlotBuilder {
             (Some(pair1), Some(pair2)) => {
                 // if pair1 has no aisles or has a locked aisle and pair2 has an aisle that is not locked, return true.
                 if pair1.get_aisles().is_empty()
-                    || pair1.get_aisles().iter().any(|a| a.locked)
-                        && pair2.get_aisles().iter().any(|a| !a.locked)
+                    || pair1.get_aisles().iter().any(locked)
+                        && pair2.get_aisles().iter().any(|a| !locked(a))
                 {
                     return Err("Stow slot is not allowed to be created".to_string());
                 }
             }
             (None, Some(pair2)) => {
                 // if pair1 doesn't exist and pair2 has no aisles or pair2 doesn't have a locked aisle, return true.
-                if pair2.get_aisles().is_empty() || pair2.get_aisles().iter().any(|a| !a.locked) {
+                if pair2.get_aisles().is_empty() || pair2.get_aisles().iter().any(|a| !locked(a)) {
                     return Err("Stow slot is not allowed to be created".to_string());
                 }
             }
@@ -724,65 +1444,636 @@ impl StowSlotBuilder {
             (_, _) => {}
         };
 
+        drop(floor);
+        if is_locked && stow_slot.locked {
+            return Err("Stow slot is already locked".to_string());
+        }
+        let before = self.snapshot();
+        let description = format!(
+            "Added {}{}",
+            stow_slot.display_range(),
+            if is_locked { " (locked)" } else { "" }
+        );
+        self.stow_slots.push(stow_slot);
         if is_locked {
-            if stow_slot.locked {
-                return Err("Stow slot is already locked".to_string());
-            }
-            stow_slot.toggle_lock();
+            self.lock_slot(self.stow_slots.len() - 1, true)?;
         }
+        self.record(description, before);
+        Ok(())
+    }
 
-        self.stow_slots.push(stow_slot);
+    /// Plans every aisle outside the locked slots. A failed run leaves the plan as it was.
+    pub fn start_algorithm(&mut self, algorithm: AlgorithmConfig) -> Result<(), String> {
+        let before = self.snapshot();
+        let description = match algorithm.algorithm {
+            Algorithm::TargetPPH => format!("Ran TargetPPH at {} PPH", algorithm.target_pph),
+            other => format!("Ran {:?} for {} stowers", other, algorithm.target_hc),
+        };
+        if let Err(e) = self.run_algorithm(algorithm) {
+            self.restore(before);
+            return Err(e);
+        }
+        let coverage = self.validate_coverage();
+        if !coverage.is_valid() {
+            self.restore(before);
+            return Err(format!("the plan does not cover the floor: {}", coverage));
+        }
+        self.record(description, before);
         Ok(())
     }
 
-    pub fn start_algorithm(&mut self, algorithm: AlgorithmConfig) {
+    fn run_algorithm(&mut self, algorithm: AlgorithmConfig) -> Result<(), String> {
+        // Locked slots are pre-assigned, everything else is rebuilt from scratch.
+        self.stow_slots.retain(|s| s.locked);
+        self.headcount_allocation = None;
+        self.algorithm_config = Some(algorithm.clone());
+        let config = algorithm.clone();
+        let floater_pph = algorithm.floater_pph;
+        let floaters_per_cluster = algorithm.floaters_per_cluster.max(0) as usize;
         match algorithm.algorithm {
             Algorithm::TargetPPH => self.start_algorithm_target_pph(algorithm),
-            Algorithm::TargetHC => self.start_algorithm_target_hc(algorithm),
+            Algorithm::TargetHC => self.start_algorithm_target_hc(algorithm)?,
+            Algorithm::Balanced => self.start_algorithm_balanced(algorithm)?,
+            Algorithm::Proportional => self.start_algorithm_proportional(algorithm)?,
+        }
+        self.sort_stow_slots();
+        if floaters_per_cluster > 0 {
+            let placed = self.place_floaters(floaters_per_cluster, floater_pph);
+            // The headcount algorithms left room for every floater in target_hc.
+            let reserved = self.reserved_floaters(&config);
+            if config.algorithm != Algorithm::TargetPPH && placed < reserved {
+                return Err(format!(
+                    "only {} of {} floaters fit under {} PPH, so the plan would miss its target headcount of {}",
+                    placed, reserved, floater_pph, config.target_hc
+                ));
+            }
+        } else {
+            self.update_floater_flags();
+        }
+        self.update_walk_distances();
+        Ok(())
+    }
+
+    /// Flags every unlocked slot at or below `floater_pph` as a floater. Plans that carve
+    /// their floaters (`floaters_per_cluster` above 0) keep the floaters they were given.
+    pub(crate) fn update_floater_flags(&mut self) {
+        let Some(config) = &self.algorithm_config else {
+            return;
+        };
+        if config.floaters_per_cluster > 0 {
+            return;
+        }
+        let floater_pph = config.floater_pph;
+        for slot in &mut self.stow_slots {
+            slot.is_floater = !slot.locked && slot.weighted_pph <= floater_pph;
+        }
+    }
+
+    /// Estimates the walk through every slot, if the layout gives walking a cost.
+    pub fn update_walk_distances(&mut self) {
+        let floor = self.floor.borrow();
+        let enabled = floor.context.layout.seconds_per_meter > 0.0;
+        for slot in &mut self.stow_slots {
+            slot.walk_distance = if enabled {
+                floor.context.walk_distance(&slot.aisles)
+            } else {
+                0.0
+            };
+        }
+    }
+
+    /// Carves `count` floater slots out of every cluster and returns how many it placed.
+    /// Each floater takes whole aisle pairs on both sides of the boundary between the two
+    /// heaviest neighbouring slots, up to `floater_pph`, so it sits between the stowers it
+    /// is expected to help. Boundaries where no pair fits under `floater_pph` are skipped.
+    pub fn place_floaters(&mut self, count: usize, floater_pph: f32) -> usize {
+        self.ensure_aisle_pairs();
+        let clusters: Vec<String> = self
+            .floor
+            .borrow()
+            .clusters
+            .iter()
+            .map(|c| c.cluster.clone())
+            .collect();
+
+        let mut placed = 0;
+        for cluster in clusters {
+            for _ in 0..count {
+                let mut neighbours: Vec<usize> = (0..self.stow_slots.len().saturating_sub(1))
+                    .filter(|&i| {
+                        let (left, right) = (&self.stow_slots[i], &self.stow_slots[i + 1]);
+                        [left, right].iter().all(|s| {
+                            s.cluster == cluster && !s.is_floater && !s.locked && s.aisles.len() > 1
+                        })
+                    })
+                    .collect();
+                let pph = |i: usize| {
+                    self.stow_slots[i].weighted_pph + self.stow_slots[i + 1].weighted_pph
+                };
+                neighbours.sort_by(|&a, &b| pph(b).total_cmp(&pph(a)));
+
+                let Some((i, floater)) = neighbours
+                    .into_iter()
+                    .find_map(|i| self.carve_floater(i, floater_pph).map(|f| (i, f)))
+                else {
+                    break;
+                };
+                self.stow_slots.insert(i + 1, floater);
+                placed += 1;
+            }
+        }
+        placed
+    }
+
+    /// Moves whole aisle pairs from the end of slot `i` and the start of slot `i + 1` into a
+    /// new floater, heavier side first, while it stays within `floater_pph`. Both slots keep
+    /// at least `min_aisle_count` pairs (one for TargetPPH). Returns `None`, leaving the
+    /// slots as they were, if not even one pair fits.
+    fn carve_floater(&mut self, i: usize, floater_pph: f32) -> Option<StowSlot> {
+        let total_hours = self.total_hours();
+        let min_pairs = match &self.algorithm_config {
+            Some(config) if config.algorithm != Algorithm::TargetPPH => config.pair_limits().min,
+            _ => 1,
+        };
+        let cluster = self.stow_slots[i].cluster.clone();
+        let mut floater = StowSlot::new(cluster, Vec::new(), total_hours);
+        loop {
+            let (left, right) = (&self.stow_slots[i], &self.stow_slots[i + 1]);
+            let left_pair = self.edge_pair_len(left, true);
+            let right_pair = self.edge_pair_len(right, false);
+            let fits = |aisles: &[Rc<Aisle>]| {
+                let pph: f32 = aisles
+                    .iter()
+                    .map(|a| a.get_aisle_weighted_pph(total_hours))
+                    .sum();
+                floater.weighted_pph + pph <= floater_pph
+            };
+            let can_take_left = self.pair_count(left) > min_pairs
+                && fits(&left.aisles[left.aisles.len() - left_pair..]);
+            let can_take_right =
+                self.pair_count(right) > min_pairs && fits(&right.aisles[..right_pair]);
+            let take_left = match (can_take_left, can_take_right) {
+                (false, false) => break,
+                (true, false) => true,
+                (false, true) => false,
+                (true, true) => left.weighted_pph >= right.weighted_pph,
+            };
+            if take_left {
+                let mut aisles = self.stow_slots[i].remove_last_aisles(left_pair);
+                aisles.append(&mut floater.aisles);
+                floater.aisles = aisles;
+            } else {
+                let aisles = self.stow_slots[i + 1].remove_first_aisles(right_pair);
+                floater.aisles.extend(aisles);
+            }
+            floater.update_pph();
+        }
+        if floater.aisles.is_empty() {
+            return None;
+        }
+        floater.is_floater = true;
+        Some(floater)
+    }
+
+    /// How many aisles at the end (or start) of the slot belong to the same aisle pair.
+    fn edge_pair_len(&self, slot: &StowSlot, at_end: bool) -> usize {
+        let floor = self.floor.borrow();
+        let pair_of = |aisle: &Rc<Aisle>| {
+            floor
+                .get_cluster(&slot.cluster)
+                .and_then(|c| c.get_pair_from_aisle(aisle))
+                .map(|(index, _)| index)
+        };
+        let mut aisles: Box<dyn Iterator<Item = &Rc<Aisle>>> = if at_end {
+            Box::new(slot.aisles.iter().rev())
+        } else {
+            Box::new(slot.aisles.iter())
+        };
+        let Some(edge) = aisles.next() else {
+            return 0;
+        };
+        let pair = pair_of(edge);
+        1 + aisles
+            .take_while(|a| pair.is_some() && pair_of(a) == pair)
+            .count()
+    }
+
+    /// Pairs every floater slot with the regular slots next to it in the same cluster.
+    pub fn floater_assignments(&self) -> Vec<(&StowSlot, Vec<&StowSlot>)> {
+        self.stow_slots
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.is_floater)
+            .map(|(i, floater)| {
+                let before = self.stow_slots[..i]
+                    .iter()
+                    .rev()
+                    .find(|s| s.cluster == floater.cluster && !s.is_floater);
+                let after = self.stow_slots[i + 1..]
+                    .iter()
+                    .find(|s| s.cluster == floater.cluster && !s.is_floater);
+                (floater, before.into_iter().chain(after).collect())
+            })
+            .collect()
+    }
+
+    pub fn display_floaters(&self) {
+        for (floater, helps) in self.floater_assignments() {
+            println!(
+                "floater {} helps {}",
+                floater.display_range(),
+                helps
+                    .iter()
+                    .map(|s| s.display_range())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
         }
     }
 
+    fn sort_stow_slots(&mut self) {
+        self.stow_slots.sort_by_key(|s| {
+            (
+                s.cluster.clone(),
+                s.aisles.iter().map(|a| a.aisle_num).min().unwrap_or(0),
+            )
+        });
+    }
+
     pub fn start_algorithm_target_pph(&mut self, algorithm: AlgorithmConfig) {
+        let total_hours = self.total_hours();
+        let context = self.floor.borrow().context.clone();
         // First collect all the aisles we need to process
-        let mut aisle_assignments: Vec<(char, Rc<Aisle>, Option<Rc<Aisle>>)> = Vec::new();
+        let mut aisle_assignments: Vec<(String, Rc<Aisle>, Option<Rc<Aisle>>)> = Vec::new();
 
         // Collect all the data we need in a separate scope to limit the borrow
         {
             // Borrow the floor immutably to collect data
             let floor = self.floor.borrow();
             for cluster in &floor.clusters {
+                let filled;
+                let cluster = if algorithm.gap_policy == GapPolicy::ZeroVolume {
+                    filled = floor.with_gaps_filled(cluster);
+                    &filled
+                } else {
+                    cluster
+                };
                 for aisle in &cluster.aisles {
-                    let previous = cluster.get_previous_aisle(aisle.aisle_num).cloned();
-                    aisle_assignments.push((cluster.cluster, Rc::clone(aisle), previous));
+                    if self.is_locked(aisle) {
+                        continue;
+                    }
+                    let previous = cluster
+                        .get_preceding_aisle(aisle.aisle_num, algorithm.gap_policy)
+                        .filter(|previous| !self.is_locked(previous))
+                        .cloned();
+                    aisle_assignments.push((cluster.cluster.clone(), Rc::clone(aisle), previous));
                 }
             }
         }
 
         // Now process the assignments without borrowing self.floor
-        for (cluster_char, aisle, previous_aisle) in aisle_assignments {
+        for (cluster_name, aisle, previous_aisle) in aisle_assignments {
             match previous_aisle {
                 Some(previous) => {
                     if let Some(existing_slot) = self.get_stow_slot_from_aisle(&previous) {
-                        if existing_slot.pph <= algorithm.target_pph as f32 {
+                        // Include the walk to the new aisle, so a long hop starts a new slot.
+                        let mut route = existing_slot.aisles.clone();
+                        route.push(Rc::clone(&aisle));
+                        let walk = context.walk_cost(context.walk_distance(&route));
+                        let pph = existing_slot.weighted_pph + walk / total_hours;
+                        if pph <= algorithm.target_pph as f32 {
                             existing_slot.add_aisle(Rc::clone(&aisle));
                             continue;
                         }
                     }
                     // Borrow floor only when needed and in a limited scope
-                    let new_slot = { StowSlot::new(cluster_char, vec![Rc::clone(&aisle)]) };
+                    let new_slot =
+                        { StowSlot::new(cluster_name, vec![Rc::clone(&aisle)], total_hours) };
                     self.stow_slots.push(new_slot);
                 }
                 None => {
                     // Borrow floor only when needed and in a limited scope
-                    let new_slot = { StowSlot::new(cluster_char, vec![Rc::clone(&aisle)]) };
+                    let new_slot =
+                        { StowSlot::new(cluster_name, vec![Rc::clone(&aisle)], total_hours) };
                     self.stow_slots.push(new_slot);
                 }
             }
         }
     }
 
-    pub fn start_algorithm_target_hc(&mut self, algorithm: AlgorithmConfig) {
-        // First generate the aisle pairs if aisle pairs are not generated.
+    pub fn start_algorithm_target_hc(&mut self, algorithm: AlgorithmConfig) -> Result<(), String> {
+        let total_hours = self.total_hours();
+        self.ensure_aisle_pairs();
+
+        let PartLimits {
+            min: min_pairs,
+            max: max_pairs,
+        } = algorithm.pair_limits();
+        let target = self.unlocked_headcount(&algorithm)?;
+        let reserved = algorithm.target_hc as usize - target;
+
+        let runs = self.unlocked_pair_runs();
+        let (run_workloads, run_links) = self.run_costs(&runs);
+        let total_pairs: usize = run_workloads.iter().map(|r| r.len()).sum();
+        if target > total_pairs {
+            return Err(format!(
+                "target headcount of {} needs more stow slots than the {} available aisle pairs",
+                algorithm.target_hc, total_pairs
+            ));
+        }
+
+        let workload = |range: &PairRange| -> f64 {
+            let links: f64 = run_links[range.run][range.start..range.end - 1]
+                .iter()
+                .sum();
+            run_workloads[range.run][range.start..range.end]
+                .iter()
+                .sum::<f64>()
+                + links
+        };
+
+        // Merge each run into as few slots as max_aisle_count allows, then split the
+        // heaviest runs one slot at a time until we reach the target headcount.
+        let mut slot_counts: Vec<usize> = run_workloads
+            .iter()
+            .map(|pairs| pairs.len().div_ceil(max_pairs).max(1))
+            .collect();
+        let needed: usize = slot_counts.iter().sum();
+        if needed > target {
+            return Err(format!(
+                "cannot reduce to {} stow slots without exceeding {} aisle pairs per slot ({} slots needed)",
+                algorithm.target_hc,
+                max_pairs,
+                needed + reserved
+            ));
+        }
+        while slot_counts.iter().sum::<usize>() < target {
+            let split = (0..run_workloads.len())
+                .filter(|&run| run_workloads[run].len() >= (slot_counts[run] + 1) * min_pairs)
+                .max_by(|&a, &b| {
+                    let average = |run: usize| {
+                        run_workloads[run].iter().sum::<f64>() / slot_counts[run] as f64
+                    };
+                    average(a).total_cmp(&average(b))
+                });
+            let Some(run) = split else {
+                return Err(format!(
+                    "cannot grow to {} stow slots without going under {} aisle pairs per slot",
+                    algorithm.target_hc, min_pairs
+                ));
+            };
+            slot_counts[run] += 1;
+        }
+
+        // Chunk every run as evenly as possible along aisle pair boundaries.
+        let mut ranges: Vec<PairRange> = Vec::new();
+        for (run, pairs) in run_workloads.iter().enumerate() {
+            let count = slot_counts[run];
+            let mut start = 0;
+            for i in 0..count {
+                let size = pairs.len() / count + usize::from(i < pairs.len() % count);
+                ranges.push(PairRange {
+                    run,
+                    start,
+                    end: start + size,
+                });
+                start += size;
+            }
+        }
+
+        // Shift boundaries between neighbouring slots while it evens out their PPH.
+        let mut improved = true;
+        while improved {
+            improved = false;
+            for i in 0..ranges.len().saturating_sub(1) {
+                if ranges[i].run != ranges[i + 1].run {
+                    continue;
+                }
+                let current = workload(&ranges[i]).max(workload(&ranges[i + 1]));
+                for at in [ranges[i].end - 1, ranges[i].end + 1] {
+                    let left = PairRange {
+                        end: at,
+                        ..ranges[i]
+                    };
+                    let right = PairRange {
+                        start: at,
+                        ..ranges[i + 1]
+                    };
+                    let sizes_ok = [left, right].iter().all(|r| {
+                        r.end > r.start && (min_pairs..=max_pairs).contains(&(r.end - r.start))
+                    });
+                    if sizes_ok && workload(&left).max(workload(&right)) < current {
+                        ranges[i] = left;
+                        ranges[i + 1] = right;
+                        improved = true;
+                        break;
+                    }
+                }
+            }
+        }
+
+        for range in ranges {
+            let (cluster, pairs) = &runs[range.run];
+            let aisles = pairs[range.start..range.end]
+                .iter()
+                .flatten()
+                .cloned()
+                .collect();
+            self.stow_slots
+                .push(StowSlot::new(cluster.clone(), aisles, total_hours));
+        }
+        Ok(())
+    }
+
+    /// Partitions the floor into the stow slots `target_hc` leaves after locked slots and
+    /// floaters, minimising the heaviest slot and then the spread of PPH between slots. The
+    /// headcount is floor-wide: each extra slot goes to whichever cluster currently has the
+    /// heaviest slot.
+    pub fn start_algorithm_balanced(&mut self, algorithm: AlgorithmConfig) -> Result<(), String> {
+        let total_hours = self.total_hours();
+        self.ensure_aisle_pairs();
+
+        let limits = algorithm.pair_limits();
+        let target = self.unlocked_headcount(&algorithm)?;
+        // Locked slots and floaters are part of the headcount but not of the partition.
+        let reserved = algorithm.target_hc as usize - target;
+        let runs = self.unlocked_pair_runs();
+        let (weights, links) = self.run_costs(&runs);
+
+        let counts = partition::allocate_parts(&weights, &links, target, limits).ok_or_else(|| {
+            let (lowest, highest) = weights
+                .iter()
+                .map(|w| limits.part_count_range(w.len()))
+                .fold((reserved, reserved), |acc, r| (acc.0 + r.0, acc.1 + r.1));
+            format!(
+                "target headcount of {} is outside the {}-{} stow slots that {}-{} aisle pairs per slot allow",
+                algorithm.target_hc, lowest, highest, limits.min, limits.max
+            )
+        })?;
+
+        let runs_with_costs = runs.iter().zip(&weights).zip(&links).zip(counts);
+        for ((((cluster, pairs), run_weights), run_links), count) in runs_with_costs {
+            let sizes = partition::balanced_partition(run_weights, run_links, count, limits)
+                .ok_or_else(|| {
+                    format!("cannot split cluster {} into {} stow slots", cluster, count)
+                })?;
+            let mut start = 0;
+            for size in sizes {
+                let aisles = pairs[start..start + size]
+                    .iter()
+                    .flatten()
+                    .cloned()
+                    .collect();
+                self.stow_slots
+                    .push(StowSlot::new(cluster.clone(), aisles, total_hours));
+                start += size;
+            }
+        }
+        Ok(())
+    }
+
+    /// Distributes `target_hc` across clusters proportionally to their workload, then
+    /// partitions every cluster into its allocated number of stow slots.
+    pub fn start_algorithm_proportional(
+        &mut self,
+        algorithm: AlgorithmConfig,
+    ) -> Result<(), String> {
+        let total_hours = self.total_hours();
+        self.ensure_aisle_pairs();
+        let limits = algorithm.pair_limits();
+        let mut allocation = self.allocate_headcount(&algorithm)?;
+        let floaters_per_cluster = algorithm.floaters_per_cluster.max(0) as usize;
+        let runs = self.unlocked_pair_runs();
+
+        for cluster in &allocation.clusters {
+            let locked = self
+                .stow_slots
+                .iter()
+                .filter(|s| s.locked && s.cluster == cluster.cluster)
+                .count();
+            let cluster_runs: Vec<(String, Vec<Vec<Rc<Aisle>>>)> = runs
+                .iter()
+                .filter(|(c, _)| *c == cluster.cluster)
+                .cloned()
+                .collect();
+            let (weights, links) = self.run_costs(&cluster_runs);
+            let regular = cluster.headcount - locked - floaters_per_cluster;
+            let counts =
+                partition::allocate_parts(&weights, &links, regular, limits).ok_or_else(|| {
+                    format!(
+                        "cannot split cluster {} into {} stow slots",
+                        cluster.cluster, regular
+                    )
+                })?;
+            let runs_with_costs = cluster_runs.iter().zip(&weights).zip(&links).zip(counts);
+            for ((((_, pairs), run_weights), run_links), count) in runs_with_costs {
+                let sizes = partition::balanced_partition(run_weights, run_links, count, limits)
+                    .ok_or_else(|| {
+                        format!(
+                            "cannot split cluster {} into {} stow slots",
+                            cluster.cluster, count
+                        )
+                    })?;
+                let mut start = 0;
+                for size in sizes {
+                    let aisles = pairs[start..start + size]
+                        .iter()
+                        .flatten()
+                        .cloned()
+                        .collect();
+                    self.stow_slots.push(StowSlot::new(
+                        cluster.cluster.clone(),
+                        aisles,
+                        total_hours,
+                    ));
+                    start += size;
+                }
+            }
+        }
+
+        allocation.update_spread(&self.stow_slots);
+        self.headcount_allocation = Some(allocation);
+        Ok(())
+    }
+
+    /// Splits the floor headcount `target_hc` between clusters proportionally to
+    /// `Cluster::get_total_workload`, within what min/max aisle pair counts allow. Every
+    /// cluster's headcount includes its locked slots and its `floaters_per_cluster`.
+    pub fn allocate_headcount(
+        &self,
+        algorithm: &AlgorithmConfig,
+    ) -> Result<HeadcountAllocation, String> {
+        self.ensure_aisle_pairs();
+        let limits = algorithm.pair_limits();
+        let floaters = algorithm.floaters_per_cluster.max(0) as usize;
+        let runs = self.unlocked_pair_runs();
+        let floor = self.floor.borrow();
+
+        let workloads: Vec<f64> = floor
+            .clusters
+            .iter()
+            .map(|c| c.get_total_workload() as f64)
+            .collect();
+        let bounds: Vec<(usize, usize)> = floor
+            .clusters
+            .iter()
+            .map(|c| {
+                let locked = self
+                    .stow_slots
+                    .iter()
+                    .filter(|s| s.locked && s.cluster == c.cluster)
+                    .count()
+                    + floaters;
+                runs.iter()
+                    .filter(|(cluster, _)| *cluster == c.cluster)
+                    .map(|(_, pairs)| limits.part_count_range(pairs.len()))
+                    .fold((locked, locked), |acc, r| (acc.0 + r.0, acc.1 + r.1))
+            })
+            .collect();
+
+        let total = algorithm.target_hc.max(0) as usize;
+        let headcounts = partition::apportion(&workloads, total, &bounds).ok_or_else(|| {
+            format!(
+                "target headcount of {} is outside the {}-{} stow slots the clusters can hold",
+                algorithm.target_hc,
+                bounds.iter().map(|b| b.0).sum::<usize>(),
+                bounds.iter().map(|b| b.1).sum::<usize>()
+            )
+        })?;
+
+        let workload_sum: f64 = workloads.iter().sum();
+        let clusters = floor
+            .clusters
+            .iter()
+            .zip(headcounts)
+            .map(|(c, headcount)| ClusterHeadcount {
+                cluster: c.cluster.clone(),
+                total_packages: c.get_total_packages(),
+                total_bags: c.get_total_bags(),
+                workload: c.get_total_workload(),
+                quota: if workload_sum > 0.0 {
+                    (total as f64 * c.get_total_workload() as f64 / workload_sum) as f32
+                } else {
+                    0.0
+                },
+                headcount,
+                min_pph: 0.0,
+                max_pph: 0.0,
+                mean_pph: 0.0,
+            })
+            .collect();
+        Ok(HeadcountAllocation {
+            total_headcount: total,
+            clusters,
+        })
+    }
+
+    pub fn total_hours(&self) -> f32 {
+        self.floor.borrow().context.total_hours
+    }
+
+    pub(crate) fn ensure_aisle_pairs(&self) {
+        // Generate the aisle pairs if aisle pairs are not generated.
         if self
             .floor
             .borrow()
@@ -794,28 +2085,381 @@ impl StowSlotBuilder {
             let mut floor = self.floor.borrow_mut();
             floor.generate_aisle_pairs();
         }
+    }
+
+    /// Headcount left for the algorithm once locked stow slots and the floaters of every
+    /// cluster are taken out of `target_hc`.
+    fn unlocked_headcount(&self, algorithm: &AlgorithmConfig) -> Result<usize, String> {
+        let locked_slots = self.stow_slots.iter().filter(|s| s.locked).count();
+        let floaters = self.reserved_floaters(algorithm);
+        let target = algorithm.target_hc.max(0) as usize;
+        if target <= locked_slots + floaters {
+            return Err(format!(
+                "target headcount {} leaves no stowers after {} locked stow slots and {} floaters",
+                algorithm.target_hc, locked_slots, floaters
+            ));
+        }
+        Ok(target - locked_slots - floaters)
+    }
 
-        // Now collect all the aisle pairs we need to process
-        let mut lowest_pph: Vec<(StowSlot, f32)> = Vec::new();
-        self.floor.borrow().clusters.iter().for_each(|c| {
-            let lowest = self.get_lowest_pph(c, 3, 3);
-            lowest_pph.extend(lowest);
-        });
-        lowest_pph.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
-        lowest_pph
+    /// Floaters that count towards `target_hc`: `floaters_per_cluster` in every cluster.
+    fn reserved_floaters(&self, algorithm: &AlgorithmConfig) -> usize {
+        algorithm.floaters_per_cluster.max(0) as usize * self.floor.borrow().clusters.len()
+    }
+
+    /// Cost of every aisle pair of each run (its workload plus the walk inside the pair)
+    /// and of the walk from each pair to the next, in package equivalents.
+    fn run_costs(&self, runs: &[(String, Vec<Vec<Rc<Aisle>>>)]) -> (Vec<Vec<f64>>, Vec<Vec<f64>>) {
+        let context = &self.floor.borrow().context;
+        let walk = |aisles: &[Rc<Aisle>]| context.walk_cost(context.walk_distance(aisles)) as f64;
+        let weights = runs
             .iter()
-            .for_each(|slot| slot.0.display_aisle_range());
-        self.fill_stow_slots_around_locked(2);
-        while self.stow_slots.len() >= algorithm.target_hc as usize {
-            let diff = self.stow_slots.len() - algorithm.target_hc as usize;
-
-            if self
-                .floor
-                .borrow()
-                .clusters
-                .iter()
-                .all(|c| c.aisle_pairs.len() % 2 == 0)
-            {}
+            .map(|(_, pairs)| {
+                pairs
+                    .iter()
+                    .map(|p| p.iter().map(|a| a.workload() as f64).sum::<f64>() + walk(p))
+                    .collect()
+            })
+            .collect();
+        let links = runs
+            .iter()
+            .map(|(_, pairs)| {
+                pairs
+                    .windows(2)
+                    .map(|w| match (w[0].last(), w[1].first()) {
+                        (Some(a), Some(b)) => walk(&[Rc::clone(a), Rc::clone(b)]),
+                        _ => 0.0,
+                    })
+                    .collect()
+            })
+            .collect();
+        (weights, links)
+    }
+
+    /// Every cluster's aisle pairs, split into runs at locked aisles and, with the `Break`
+    /// gap policy, at gaps in the aisle numbering. With `ZeroVolume` the gaps are filled
+    /// with empty aisles for this run only.
+    fn unlocked_pair_runs(&self) -> Vec<(String, Vec<Vec<Rc<Aisle>>>)> {
+        let floor = self.floor.borrow();
+        let gap_policy = self
+            .algorithm_config
+            .as_ref()
+            .map(|c| c.gap_policy)
+            .unwrap_or_default();
+        let break_at_gaps = gap_policy == GapPolicy::Break;
+        let mut runs = Vec::new();
+        for cluster in &floor.clusters {
+            let pairs = if gap_policy == GapPolicy::ZeroVolume {
+                floor.with_gaps_filled(cluster).aisle_pairs
+            } else {
+                cluster.aisle_pairs.clone()
+            };
+            let mut run: Vec<Vec<Rc<Aisle>>> = Vec::new();
+            for pair in &pairs {
+                let aisles = pair.get_aisles();
+                if let Some(locked) = aisles.iter().find(|a| self.is_locked(a)) {
+                    // The pair's other aisle is still planned, on its side of the lock.
+                    let (before, after): (Vec<_>, Vec<_>) = aisles
+                        .iter()
+                        .filter(|a| !self.is_locked(a))
+                        .cloned()
+                        .partition(|a| a.aisle_num < locked.aisle_num);
+                    if !before.is_empty() {
+                        run.push(before);
+                    }
+                    if !run.is_empty() {
+                        runs.push((cluster.cluster.clone(), std::mem::take(&mut run)));
+                    }
+                    if !after.is_empty() {
+                        run.push(after);
+                    }
+                    continue;
+                }
+                let previous_end = run.last().and_then(|p| p.iter().map(|a| a.aisle_num).max());
+                let start = aisles.iter().map(|a| a.aisle_num).min();
+                if let (true, Some(end), Some(start)) = (break_at_gaps, previous_end, start) {
+                    if start > end + 1 {
+                        runs.push((cluster.cluster.clone(), std::mem::take(&mut run)));
+                    }
+                }
+                run.push(aisles);
+            }
+            if !run.is_empty() {
+                runs.push((cluster.cluster.clone(), run));
+            }
+        }
+        runs
+    }
+}
+
+/// A contiguous range of aisle pairs `start..end` inside one unlocked run of a cluster.
+#[derive(Debug, Clone, Copy)]
+struct PairRange {
+    run: usize,
+    start: usize,
+    end: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Algorithm, AlgorithmConfig, Assignment, BagRecord, Floor, GapPolicy, SortZoneSchema,
+        StowSlotBuilder,
+    };
+    use crate::test_support::{bag, builder, context, floor, floor_with_layout, plan};
+
+    /// Cluster A with aisles 1-12 (six pairs) of uneven volume.
+    fn six_pairs() -> StowSlotBuilder {
+        let aisles: Vec<(u32, i32)> = (1..=12).map(|n| (n, 10 + (n as i32 % 5) * 7)).collect();
+        floor(&[("A", &aisles)]).create_stow_slot_builder()
+    }
+
+    fn target_hc(target_hc: i32, min: i32, max: i32) -> AlgorithmConfig {
+        AlgorithmConfig {
+            algorithm: Algorithm::TargetHC,
+            target_hc,
+            min_aisle_count: min,
+            max_aisle_count: max,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn target_hc_plans_exactly_the_headcount() {
+        for hc in 2..=6 {
+            let mut builder = six_pairs();
+            builder.start_algorithm(target_hc(hc, 1, 3)).unwrap();
+            assert_eq!(builder.stow_slots.len(), hc as usize, "hc {}", hc);
+            assert!(builder.validate_coverage().is_valid(), "hc {}", hc);
+            // Slots hold whole pairs within the limits.
+            for slot in &builder.stow_slots {
+                assert!(slot.aisles.len() % 2 == 0 && (2..=6).contains(&slot.aisles.len()));
+            }
+        }
+    }
+
+    #[test]
+    fn floaters_leave_their_neighbours_the_minimum_pairs() {
+        let balanced = |hc: i32, min: i32| AlgorithmConfig {
+            algorithm: Algorithm::Balanced,
+            target_hc: hc,
+            min_aisle_count: min,
+            max_aisle_count: 3,
+            floaters_per_cluster: 1,
+            floater_pph: 1000.0,
+            ..Default::default()
+        };
+        // Three regular slots of two pairs each cannot give a pair away.
+        let mut builder = six_pairs();
+        assert_eq!(
+            builder.start_algorithm(balanced(4, 2)),
+            Err("only 0 of 1 floaters fit under 1000 PPH, so the plan would miss its target headcount of 4".to_string())
+        );
+        assert!(builder.stow_slots.is_empty());
+
+        let mut builder = six_pairs();
+        builder.start_algorithm(balanced(4, 1)).unwrap();
+        assert_eq!(builder.stow_slots.len(), 4);
+        assert_eq!(
+            builder.stow_slots.iter().filter(|s| s.is_floater).count(),
+            1
+        );
+
+        let mut builder = six_pairs();
+        builder.start_algorithm(balanced(3, 2)).unwrap();
+        for slot in builder.stow_slots.iter().filter(|s| !s.is_floater) {
+            assert!(builder.pair_count(slot) >= 2, "{}", slot.display_range());
+        }
+    }
+
+    #[test]
+    fn target_hc_rejects_infeasible_headcounts() {
+        let cases = [
+            (1, 1, 3, "cannot reduce to 1 stow slots without exceeding 3 aisle pairs per slot (2 slots needed)"),
+            (7, 1, 3, "target headcount of 7 needs more stow slots than the 6 available aisle pairs"),
+            (4, 2, 3, "cannot grow to 4 stow slots without going under 2 aisle pairs per slot"),
+        ];
+        for (hc, min, max, expected) in cases {
+            let mut builder = six_pairs();
+            assert_eq!(
+                builder.start_algorithm(target_hc(hc, min, max)),
+                Err(expected.to_string())
+            );
+            assert!(builder.stow_slots.is_empty());
+        }
+    }
+
+    #[test]
+    fn proportional_headcounts_add_up_to_the_target() {
+        let a: Vec<(u32, i32)> = (1..=12).map(|n| (n, 40 + n as i32)).collect();
+        let b: Vec<(u32, i32)> = (1..=8).map(|n| (n, 20)).collect();
+        for (target_hc, floaters_per_cluster) in [(5, 0), (6, 0), (7, 1), (8, 1)] {
+            let mut builder = floor(&[("A", &a), ("B", &b)]).create_stow_slot_builder();
+            builder
+                .start_algorithm(AlgorithmConfig {
+                    algorithm: Algorithm::Proportional,
+                    target_hc,
+                    min_aisle_count: 1,
+                    max_aisle_count: 3,
+                    floaters_per_cluster,
+                    ..Default::default()
+                })
+                .unwrap();
+            let allocation = builder.headcount_allocation.as_ref().unwrap();
+            let headcounts: usize = allocation.clusters.iter().map(|c| c.headcount).sum();
+            assert_eq!(headcounts, target_hc as usize);
+            for cluster in &allocation.clusters {
+                let slots = builder
+                    .stow_slots
+                    .iter()
+                    .filter(|s| s.cluster == cluster.cluster)
+                    .count();
+                assert_eq!(slots, cluster.headcount, "cluster {}", cluster.cluster);
+            }
         }
     }
+
+    #[test]
+    fn workload_weights_scale_weighted_pph() {
+        let bags = vec![bag("A", 1, 10), bag("A", 2, 20)];
+        let mut heavy = context();
+        heavy.workload.levels.insert("1".to_string(), 2.0);
+        heavy.workload.columns.insert("a".to_string(), 1.5);
+        let weighted = builder(Floor::new(bags.clone(), heavy), &[("A", &[1, 2])]);
+        let plain = builder(Floor::new(bags, context()), &[("A", &[1, 2])]);
+
+        assert_eq!(plain.stow_slots[0].pph, 30.0);
+        assert_eq!(plain.stow_slots[0].weighted_pph, 30.0);
+        assert_eq!(weighted.stow_slots[0].pph, 30.0);
+        assert_eq!(weighted.stow_slots[0].weighted_pph, 90.0);
+    }
+
+    #[test]
+    fn workload_weights_move_slot_boundaries() {
+        // Aisles 1 and 2 are stowed into column B, which takes three times as long.
+        let bags: Vec<BagRecord> = (1..=8)
+            .map(|aisle| {
+                let mut bag = bag("A", aisle, 10);
+                if aisle <= 2 {
+                    bag.sort_zone.column = "B".to_string();
+                }
+                bag
+            })
+            .collect();
+        let balanced = AlgorithmConfig {
+            algorithm: Algorithm::Balanced,
+            target_hc: 2,
+            min_aisle_count: 1,
+            max_aisle_count: 3,
+            ..Default::default()
+        };
+        let mut plain = Floor::new(bags.clone(), context()).create_stow_slot_builder();
+        plain.start_algorithm(balanced.clone()).unwrap();
+        assert_eq!(plan(&plain), [vec![1, 2, 3, 4], vec![5, 6, 7, 8]]);
+
+        let mut heavy = context();
+        heavy.workload.columns.insert("B".to_string(), 3.0);
+        let mut weighted = Floor::new(bags, heavy).create_stow_slot_builder();
+        weighted.start_algorithm(balanced).unwrap();
+        assert_eq!(plan(&weighted), [vec![1, 2], vec![3, 4, 5, 6, 7, 8]]);
+    }
+
+    #[test]
+    fn assign_rejects_ranges_that_split_facing_aisles() {
+        let aisles: Vec<(u32, i32)> = (1..=10).map(|n| (n, 10)).collect();
+        let mut builder = floor(&[("A", &aisles)]).create_stow_slot_builder();
+        let error = builder
+            .assign(&Assignment::new("Sam", "A-8..A-9").unwrap())
+            .unwrap_err();
+        assert_eq!(
+            error,
+            "Sam: A-8..A-9 splits the facing aisles A-7 & A-8, A-9 & A-10; assign whole pairs, e.g. A-7..A-10"
+        );
+        assert!(builder.stow_slots.is_empty());
+
+        builder
+            .assign(&Assignment::new("Sam", "A-7..A-10").unwrap())
+            .unwrap();
+        assert_eq!(plan(&builder), [vec![7, 8, 9, 10]]);
+        assert!(builder.stow_slots[0].locked);
+    }
+
+    #[test]
+    fn assign_takes_the_pairs_at_either_end_of_a_cluster() {
+        let aisles: Vec<(u32, i32)> = (1..=6).map(|n| (n, 10)).collect();
+        let mut builder = floor(&[("A", &aisles)]).create_stow_slot_builder();
+        builder
+            .assign(&Assignment::new("Sam", "A-1..A-2").unwrap())
+            .unwrap();
+        builder
+            .assign(&Assignment::new("Alex", "A-5..A-6").unwrap())
+            .unwrap();
+        assert_eq!(plan(&builder), [vec![1, 2], vec![5, 6]]);
+        assert!(builder.stow_slots.iter().all(|s| s.locked));
+    }
+
+    #[test]
+    fn zero_volume_slots_flag_the_gap_and_keep_phantoms_out_of_the_outputs() {
+        let aisles = [(1, 100), (2, 100), (3, 100), (4, 100), (7, 100), (8, 100)];
+        let mut builder = floor(&[("A", &aisles)]).create_stow_slot_builder();
+        builder
+            .start_algorithm(AlgorithmConfig {
+                gap_policy: GapPolicy::ZeroVolume,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(plan(&builder), [vec![1, 2, 3, 4, 5, 6, 7, 8]]);
+        assert_eq!(builder.stow_slots[0].gaps(), [5, 6]);
+        assert_eq!(builder.stow_slots[0].display_range(), "A-1 - A-8");
+
+        let rows = builder.stow_slot_rows();
+        assert_eq!(rows[0].aisles, "A-1, A-2, A-3, A-4, A-7, A-8");
+        assert_eq!(rows[0].gaps, "A-5, A-6");
+        assert!(builder.validate_coverage().is_valid());
+        assert_eq!(GapPolicy::default(), GapPolicy::Break);
+    }
+
+    #[test]
+    fn zero_volume_keeps_aisles_the_layout_declares() {
+        let aisles = [(1, 100), (2, 100), (5, 100), (6, 100)];
+        let layout = "[clusters.A]\nrange = [1, 4]\n";
+        let mut builder = floor_with_layout(&[("A", &aisles)], layout).create_stow_slot_builder();
+        builder
+            .start_algorithm(AlgorithmConfig {
+                gap_policy: GapPolicy::ZeroVolume,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(plan(&builder), [vec![1, 2, 3, 4, 5, 6]]);
+        assert!(builder.stow_slots[0].gaps().is_empty());
+        assert_eq!(
+            builder.stow_slot_rows()[0].aisles,
+            "A-1, A-2, A-3, A-4, A-5, A-6"
+        );
+    }
+
+    #[test]
+    fn sort_zone_displays_as_the_input_spelled_it() {
+        let schema =
+            SortZoneSchema::new(r"^(?P<cluster>[A-Z])(?P<aisle>\d+)-L(?P<level>\d+)$").unwrap();
+        let zone = schema.parse(" B12-L3 ").unwrap();
+        assert_eq!(
+            (zone.cluster.as_str(), zone.aisle, zone.level),
+            ("B", 12, 3)
+        );
+        assert_eq!(zone.display(), "B12-L3");
+    }
+
+    #[test]
+    fn aisle_pair_get_aisles_returns_both_aisles() {
+        let mut floor = floor(&[("A", &[(1, 10), (2, 20), (3, 30)])]);
+        floor.generate_aisle_pairs();
+        let cluster = floor.get_cluster("A").unwrap();
+        let aisles: Vec<Vec<u32>> = cluster
+            .aisle_pairs
+            .iter()
+            .map(|p| p.get_aisles().iter().map(|a| a.aisle_num).collect())
+            .collect();
+        assert_eq!(aisles, [vec![1, 2], vec![3]]);
+    }
 }
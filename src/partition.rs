@@ -0,0 +1,409 @@
+//! Optimal contiguous partitioning of ordered workloads.
+//!
+//! A run is an ordered list of unit weights (e.g. the packages of each aisle pair in a
+//! cluster). Units can only be grouped with their neighbours, so every part is a
+//! contiguous range of the run. Optional links give the cost of joining each unit to the
+//! next (e.g. the walk between two aisle pairs); a part pays for the links inside it, so
+//! expensive links make natural boundaries. An empty link list means every link is free.
+
+/// Size limits for every part, measured in units.
+#[derive(Debug, Clone, Copy)]
+pub struct PartLimits {
+    pub min: usize,
+    pub max: usize,
+}
+
+impl PartLimits {
+    pub fn new(min: usize, max: usize) -> Self {
+        let min = min.max(1);
+        Self {
+            min,
+            max: max.max(min),
+        }
+    }
+
+    /// Smallest and largest number of parts a run of `len` units can be split into.
+    ///
+    /// A run shorter than `min` can still be covered by a single undersized part.
+    pub fn part_count_range(&self, len: usize) -> (usize, usize) {
+        if len == 0 {
+            return (0, 0);
+        }
+        if len < self.min {
+            return (1, 1);
+        }
+        (len.div_ceil(self.max), len / self.min)
+    }
+}
+
+/// Prefix sums for the cost of any contiguous part of a run.
+struct PartCosts {
+    weights: Vec<f64>,
+    links: Vec<f64>,
+}
+
+impl PartCosts {
+    fn new(weights: &[f64], links: &[f64]) -> Self {
+        let prefix = |values: &mut dyn Iterator<Item = f64>| {
+            let mut sums = vec![0.0];
+            for value in values {
+                sums.push(sums[sums.len() - 1] + value);
+            }
+            sums
+        };
+        Self {
+            weights: prefix(&mut weights.iter().copied()),
+            links: prefix(
+                &mut (0..weights.len().saturating_sub(1))
+                    .map(|i| links.get(i).copied().unwrap_or(0.0)),
+            ),
+        }
+    }
+
+    /// Cost of units `i..j`: their weights plus the `j - i - 1` links between them.
+    fn part(&self, i: usize, j: usize) -> f64 {
+        self.weights[j] - self.weights[i] + self.links[j - 1] - self.links[i]
+    }
+}
+
+/// Splits `weights` into exactly `parts` contiguous parts.
+///
+/// The heaviest part is minimised first; among all partitions with that bottleneck the
+/// sum of squared part weights (i.e. the variance) is minimised. Returns the size of every
+/// part in order, or `None` when `parts` is outside [`PartLimits::part_count_range`].
+pub fn balanced_partition(
+    weights: &[f64],
+    links: &[f64],
+    parts: usize,
+    limits: PartLimits,
+) -> Option<Vec<usize>> {
+    let n = weights.len();
+    let (lowest, highest) = limits.part_count_range(n);
+    if parts < lowest || parts > highest || parts == 0 {
+        return None;
+    }
+    if parts == 1 {
+        return Some(vec![n]);
+    }
+
+    let costs = PartCosts::new(weights, links);
+    let sum = |i: usize, j: usize| costs.part(i, j);
+    let sizes = limits.min..=limits.max;
+
+    // bottleneck[k][j]: lightest possible heaviest part when the first j units form k parts.
+    let mut bottleneck = vec![vec![f64::INFINITY; n + 1]; parts + 1];
+    bottleneck[0][0] = 0.0;
+    for k in 1..=parts {
+        for j in 1..=n {
+            for size in sizes.clone().take_while(|&size| size <= j) {
+                let previous = bottleneck[k - 1][j - size];
+                if previous.is_finite() {
+                    let candidate = previous.max(sum(j - size, j));
+                    bottleneck[k][j] = bottleneck[k][j].min(candidate);
+                }
+            }
+        }
+    }
+    let limit = bottleneck[parts][n];
+    if !limit.is_finite() {
+        return None;
+    }
+    let limit = limit + 1e-9 * limit.abs().max(1.0);
+
+    // squares[k][j]: smallest sum of squares for the same prefix without exceeding the bottleneck.
+    let mut squares = vec![vec![f64::INFINITY; n + 1]; parts + 1];
+    let mut choice = vec![vec![0; n + 1]; parts + 1];
+    squares[0][0] = 0.0;
+    for k in 1..=parts {
+        for j in 1..=n {
+            for size in sizes.clone().take_while(|&size| size <= j) {
+                let part = sum(j - size, j);
+                let previous = squares[k - 1][j - size];
+                if part > limit || !previous.is_finite() {
+                    continue;
+                }
+                let candidate = previous + part * part;
+                if candidate < squares[k][j] {
+                    squares[k][j] = candidate;
+                    choice[k][j] = size;
+                }
+            }
+        }
+    }
+
+    let mut result = Vec::with_capacity(parts);
+    let mut j = n;
+    for k in (1..=parts).rev() {
+        let size = choice[k][j];
+        result.push(size);
+        j -= size;
+    }
+    result.reverse();
+    Some(result)
+}
+
+/// The heaviest part produced by [`balanced_partition`].
+pub fn bottleneck(weights: &[f64], links: &[f64], parts: usize, limits: PartLimits) -> Option<f64> {
+    let sizes = balanced_partition(weights, links, parts, limits)?;
+    let costs = PartCosts::new(weights, links);
+    let mut start = 0;
+    let mut heaviest: f64 = 0.0;
+    for size in sizes {
+        heaviest = heaviest.max(costs.part(start, start + size));
+        start += size;
+    }
+    Some(heaviest)
+}
+
+/// Distributes exactly `total` parts across independent runs.
+///
+/// Every run gets the fewest parts its limits allow, then the extra parts go one at a time
+/// to whichever run currently has the heaviest part. `links` holds the links of every run
+/// (or nothing). Returns the part count per run, or `None` when `total` is outside what
+/// the runs can hold.
+pub fn allocate_parts(
+    runs: &[Vec<f64>],
+    links: &[Vec<f64>],
+    total: usize,
+    limits: PartLimits,
+) -> Option<Vec<usize>> {
+    let run_links = |run: usize| links.get(run).map(Vec::as_slice).unwrap_or(&[]);
+    let ranges: Vec<(usize, usize)> = runs
+        .iter()
+        .map(|run| limits.part_count_range(run.len()))
+        .collect();
+    let lowest: usize = ranges.iter().map(|r| r.0).sum();
+    let highest: usize = ranges.iter().map(|r| r.1).sum();
+    if total < lowest || total > highest {
+        return None;
+    }
+
+    let mut counts: Vec<usize> = ranges.iter().map(|r| r.0).collect();
+    let mut heaviest: Vec<f64> = runs
+        .iter()
+        .zip(&counts)
+        .enumerate()
+        .map(|(i, (run, &count))| bottleneck(run, run_links(i), count, limits).unwrap_or(0.0))
+        .collect();
+    for _ in lowest..total {
+        let run = (0..runs.len())
+            .filter(|&i| counts[i] < ranges[i].1)
+            .max_by(|&a, &b| heaviest[a].total_cmp(&heaviest[b]))?;
+        counts[run] += 1;
+        heaviest[run] = bottleneck(&runs[run], run_links(run), counts[run], limits).unwrap_or(0.0);
+    }
+    Some(counts)
+}
+
+/// Apportions `total` seats proportionally to `weights` while keeping every share inside
+/// its `(min, max)` bounds.
+///
+/// Each share starts at its minimum and the remaining seats go one at a time to whichever
+/// share is furthest below its exact proportional quota (the largest remainder method).
+pub fn apportion(weights: &[f64], total: usize, bounds: &[(usize, usize)]) -> Option<Vec<usize>> {
+    let lowest: usize = bounds.iter().map(|b| b.0).sum();
+    let highest: usize = bounds.iter().map(|b| b.1).sum();
+    if total < lowest || total > highest {
+        return None;
+    }
+
+    let weight_sum: f64 = weights.iter().sum();
+    let quota = |i: usize| {
+        if weight_sum > 0.0 {
+            total as f64 * weights[i] / weight_sum
+        } else {
+            total as f64 / weights.len() as f64
+        }
+    };
+    let mut seats: Vec<usize> = bounds.iter().map(|b| b.0).collect();
+    for _ in lowest..total {
+        let next = (0..weights.len())
+            .filter(|&i| seats[i] < bounds[i].1)
+            .max_by(|&a, &b| {
+                (quota(a) - seats[a] as f64).total_cmp(&(quota(b) - seats[b] as f64))
+            })?;
+        seats[next] += 1;
+    }
+    Some(seats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `(name, weights, links, parts, min, max, expected sizes)`
+    type PartitionCase<'a> = (
+        &'a str,
+        &'a [f64],
+        &'a [f64],
+        usize,
+        usize,
+        usize,
+        Option<&'a [usize]>,
+    );
+
+    /// `(name, weights, total, bounds, expected seats)`
+    type ApportionCase<'a> = (
+        &'a str,
+        &'a [f64],
+        usize,
+        &'a [(usize, usize)],
+        Option<&'a [usize]>,
+    );
+
+    #[test]
+    fn part_count_range() {
+        // (len, min, max, expected)
+        let cases = [
+            (0, 2, 3, (0, 0)),
+            (1, 2, 3, (1, 1)),
+            (6, 2, 3, (2, 3)),
+            (7, 2, 3, (3, 3)),
+            (12, 2, 3, (4, 6)),
+        ];
+        for (len, min, max, expected) in cases {
+            let range = PartLimits::new(min, max).part_count_range(len);
+            assert_eq!(range, expected, "len {} with {}-{}", len, min, max);
+        }
+    }
+
+    #[test]
+    fn balanced_partition_cases() {
+        let cases: [PartitionCase; 12] = [
+            ("even split", &[5.0; 4], &[], 2, 1, 4, Some(&[2, 2])),
+            ("single part", &[1.0, 2.0, 3.0], &[], 1, 1, 3, Some(&[3])),
+            (
+                "heavy ends",
+                &[9.0, 1.0, 1.0, 1.0, 9.0],
+                &[],
+                3,
+                1,
+                5,
+                Some(&[1, 3, 1]),
+            ),
+            // [1, 1, 3] has the same bottleneck of 5 but a larger sum of squares.
+            (
+                "min squares",
+                &[5.0, 1.0, 1.0, 1.0, 1.0],
+                &[],
+                3,
+                1,
+                5,
+                Some(&[1, 2, 2]),
+            ),
+            // [2, 1] and [1, 2] both weigh 3 and 1; the last part is kept small.
+            ("tie", &[1.0, 2.0, 1.0], &[], 2, 1, 3, Some(&[2, 1])),
+            (
+                "costly link",
+                &[1.0; 4],
+                &[10.0, 0.0, 0.0],
+                2,
+                1,
+                4,
+                Some(&[1, 3]),
+            ),
+            ("max size", &[1.0; 6], &[], 2, 1, 3, Some(&[3, 3])),
+            ("undersized run", &[1.0], &[], 1, 2, 3, Some(&[1])),
+            ("no parts", &[1.0; 3], &[], 0, 1, 3, None),
+            ("more parts than units", &[1.0; 3], &[], 4, 1, 3, None),
+            ("above max size", &[1.0; 3], &[], 2, 1, 1, None),
+            ("below min size", &[1.0; 6], &[], 2, 4, 6, None),
+        ];
+        for (name, weights, links, parts, min, max, expected) in cases {
+            let sizes = balanced_partition(weights, links, parts, PartLimits::new(min, max));
+            assert_eq!(sizes.as_deref(), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn bottleneck_is_the_heaviest_part() {
+        let limits = PartLimits::new(1, 5);
+        let weights = [5.0, 1.0, 1.0, 1.0, 1.0];
+        assert_eq!(bottleneck(&weights, &[], 3, limits), Some(5.0));
+        assert_eq!(bottleneck(&weights, &[], 1, limits), Some(9.0));
+        // Links inside a part count towards it.
+        assert_eq!(
+            bottleneck(&[1.0; 4], &[10.0, 0.0, 0.0], 1, limits),
+            Some(14.0)
+        );
+        assert_eq!(bottleneck(&weights, &[], 6, limits), None);
+    }
+
+    #[test]
+    fn allocate_parts_cases() {
+        let limits = PartLimits::new(1, 4);
+        let runs = vec![vec![10.0; 4], vec![1.0; 4]];
+        // (total, expected parts per run)
+        let cases: [(usize, Option<Vec<usize>>); 5] = [
+            (2, Some(vec![1, 1])),
+            (3, Some(vec![2, 1])),
+            (5, Some(vec![4, 1])),
+            (1, None),
+            (9, None),
+        ];
+        for (total, expected) in cases {
+            assert_eq!(
+                allocate_parts(&runs, &[], total, limits),
+                expected,
+                "{} parts",
+                total
+            );
+        }
+    }
+
+    #[test]
+    fn apportion_cases() {
+        let cases: [ApportionCase; 8] = [
+            ("exact", &[1.0, 1.0], 4, &[(0, 4), (0, 4)], Some(&[2, 2])),
+            (
+                "proportional",
+                &[3.0, 1.0],
+                4,
+                &[(0, 4), (0, 4)],
+                Some(&[3, 1]),
+            ),
+            (
+                "largest remainder",
+                &[2.0, 1.0],
+                2,
+                &[(0, 2), (0, 2)],
+                Some(&[1, 1]),
+            ),
+            ("capped", &[3.0, 1.0], 4, &[(0, 2), (0, 4)], Some(&[2, 2])),
+            ("minimum", &[1.0, 0.0], 2, &[(0, 2), (1, 2)], Some(&[1, 1])),
+            (
+                "no weight",
+                &[0.0, 0.0],
+                2,
+                &[(0, 2), (0, 2)],
+                Some(&[1, 1]),
+            ),
+            (
+                "above the maximums",
+                &[1.0, 1.0],
+                5,
+                &[(0, 2), (0, 2)],
+                None,
+            ),
+            (
+                "below the minimums",
+                &[1.0, 1.0],
+                1,
+                &[(1, 2), (1, 2)],
+                None,
+            ),
+        ];
+        for (name, weights, total, bounds, expected) in cases {
+            let seats = apportion(weights, total, bounds);
+            assert_eq!(seats.as_deref(), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn apportion_breaks_ties_towards_the_last_share() {
+        assert_eq!(
+            apportion(&[1.0, 1.0, 1.0], 1, &[(0, 1); 3]),
+            Some(vec![0, 0, 1])
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use crate::models::{StowSlot, StowSlotBuilder};
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const STYLE: &str = "
+body { font-family: sans-serif; font-size: 12pt; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 1em; }
+th, td { border: 1px solid #444; padding: 2px 6px; text-align: left; }
+td.num, th.num { text-align: right; }
+section { page-break-before: always; }
+.floater { font-style: italic; }
+@media print { h1 { margin-top: 0; } }
+";
+
+/// Renders a printable HTML document: a one-page roster of every slot per cluster,
+/// followed by one page per stow slot listing its sort zones.
+pub fn assignment_sheets(builder: &StowSlotBuilder) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>Stow Assignments</title>\n");
+    let _ = writeln!(html, "<style>{}</style>\n</head>\n<body>", STYLE);
+
+    html.push_str("<h1>Stow Roster</h1>\n");
+    let clusters: BTreeSet<&str> = builder
+        .stow_slots
+        .iter()
+        .map(|s| s.cluster.as_str())
+        .collect();
+    for cluster in clusters {
+        let _ = writeln!(html, "<h2>Cluster {}</h2>", escape(cluster));
+        html.push_str("<table>\n<tr><th>Slot</th><th>Aisles</th><th class=\"num\">Packages</th><th class=\"num\">Bags</th><th class=\"num\">PPH</th><th>Stower</th></tr>\n");
+        for (i, slot) in builder.stow_slots.iter().enumerate() {
+            if slot.cluster != cluster {
+                continue;
+            }
+            let _ = writeln!(
+                html,
+                "<tr{}><td>{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td>{}</td></tr>",
+                floater_class(slot),
+                i + 1,
+                escape(&slot_label(slot)),
+                slot.total_packages(),
+                slot.total_bags(),
+                slot.pph as i32,
+                escape(slot.stower.as_deref().unwrap_or_default())
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    let floaters = builder.floater_assignments();
+    for (i, slot) in builder.stow_slots.iter().enumerate() {
+        html.push_str("<section>\n");
+        let _ = writeln!(
+            html,
+            "<h1>Slot {} &mdash; Cluster {}</h1>\n<p><strong>{}</strong></p>",
+            i + 1,
+            escape(&slot.cluster),
+            escape(&slot_label(slot))
+        );
+        if let Some(stower) = &slot.stower {
+            let _ = writeln!(html, "<p>Stower: <strong>{}</strong></p>", escape(stower));
+        }
+        let _ = writeln!(
+            html,
+            "<p>Expected PPH: <strong>{}</strong> &middot; Packages: {} &middot; Bags: {}</p>",
+            slot.pph as i32,
+            slot.total_packages(),
+            slot.total_bags()
+        );
+        let gaps = slot.gap_labels();
+        if !gaps.is_empty() {
+            let _ = writeln!(
+                html,
+                "<p><strong>Spans a gap:</strong> no aisle {}</p>",
+                escape(&gaps.join(", "))
+            );
+        }
+        let sources: Vec<String> = slot
+            .pph_by_source()
+            .iter()
+            .map(|(source, pph)| format!("{}: {}", escape(source), *pph as i32))
+            .collect();
+        if !sources.is_empty() {
+            let _ = writeln!(html, "<p>PPH by wave: {}</p>", sources.join(" &middot; "));
+        }
+        if let Some((_, helps)) = floaters.iter().find(|(f, _)| std::ptr::eq(*f, slot)) {
+            let helps: Vec<String> = helps.iter().map(|s| escape(&s.display_range())).collect();
+            let _ = writeln!(html, "<p>Floater, helps: {}</p>", helps.join(", "));
+        }
+        html.push_str("<table>\n<tr><th>Sort Zone</th><th class=\"num\">Planned Bags</th><th class=\"num\">Planned Packages</th></tr>\n");
+        for aisle in &slot.aisles {
+            let mut records: Vec<_> = aisle.bag_records.iter().collect();
+            records.sort_by(|a, b| {
+                (a.sort_zone.level, &a.sort_zone.column)
+                    .cmp(&(b.sort_zone.level, &b.sort_zone.column))
+            });
+            for record in records {
+                let _ = writeln!(
+                    html,
+                    "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+                    escape(&record.sort_zone.display()),
+                    record.planned_bag_count,
+                    record.planned_package_count
+                );
+            }
+        }
+        html.push_str("</table>\n</section>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+pub fn write_assignment_sheets<P: AsRef<Path>>(
+    builder: &StowSlotBuilder,
+    path: P,
+) -> Result<(), Box<dyn Error>> {
+    fs::write(path, assignment_sheets(builder))?;
+    Ok(())
+}
+
+/// `plan.csv` becomes `plan_sheets.html` in the same directory.
+pub fn default_sheets_path(input: &Path) -> PathBuf {
+    let stem = input
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "plan".to_string());
+    input.with_file_name(format!("{}_sheets.html", stem))
+}
+
+fn slot_label(slot: &StowSlot) -> String {
+    if slot.aisles.is_empty() {
+        return "no aisles".to_string();
+    }
+    let mut label = slot.display_range();
+    if slot.is_floater {
+        label.push_str(" (floater)");
+    }
+    label
+}
+
+fn floater_class(slot: &StowSlot) -> &'static str {
+    if slot.is_floater {
+        " class=\"floater\""
+    } else {
+        ""
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{builder, floor};
+
+    #[test]
+    fn roster_lists_each_cluster_once_with_its_name_escaped() {
+        let f = floor(&[("<A>", &[(1, 10), (2, 10)]), ("B", &[(1, 10), (2, 10)])]);
+        let b = builder(f, &[("<A>", &[1]), ("B", &[1, 2]), ("<A>", &[2])]);
+        let html = assignment_sheets(&b);
+        assert_eq!(html.matches("<h2>Cluster &lt;A&gt;</h2>").count(), 1);
+        assert_eq!(html.matches("<h2>Cluster B</h2>").count(), 1);
+        assert!(html.contains("&mdash; Cluster &lt;A&gt;</h1>"));
+        assert!(!html.contains("<A>"));
+    }
+}
@@ -0,0 +1,215 @@
+//! How even a finished plan is, in numbers that can be compared between algorithms.
+//!
+//! Spread statistics use the weighted PPH of the regular slots; floaters are light on
+//! purpose and only counted. The target is `target_pph` for TargetPPH plans and the mean
+//! regular slot otherwise.
+
+use crate::models::{Algorithm, StowSlot, StowSlotBuilder};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scorecard {
+    pub slots: usize,
+    pub floaters: usize,
+    pub target_pph: f32,
+    /// Allowed deviation from the target, as a fraction of it.
+    pub tolerance: f32,
+    pub spread: PphSpread,
+    /// Regular slots more than `tolerance` away from the target.
+    pub outside_tolerance: usize,
+    /// Slots whose aisle numbers skip.
+    pub non_consecutive: usize,
+    /// Aisles on the floor that no slot covers.
+    pub unassigned_aisles: Vec<String>,
+    pub clusters: Vec<ClusterScorecard>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterScorecard {
+    pub cluster: String,
+    pub slots: usize,
+    pub floaters: usize,
+    pub spread: PphSpread,
+    pub outside_tolerance: usize,
+    pub non_consecutive: usize,
+    pub unassigned_aisles: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PphSpread {
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+    pub std_dev: f32,
+    /// Standard deviation over mean; 0 is a perfectly even plan.
+    pub coefficient_of_variation: f32,
+}
+
+impl PphSpread {
+    pub fn new(pph: &[f32]) -> Self {
+        if pph.is_empty() {
+            return Self::default();
+        }
+        let mean = pph.iter().sum::<f32>() / pph.len() as f32;
+        let variance = pph.iter().map(|p| (p - mean).powi(2)).sum::<f32>() / pph.len() as f32;
+        let std_dev = variance.sqrt();
+        Self {
+            min: pph.iter().cloned().fold(f32::INFINITY, f32::min),
+            max: pph.iter().cloned().fold(0.0, f32::max),
+            mean,
+            std_dev,
+            coefficient_of_variation: if mean > 0.0 { std_dev / mean } else { 0.0 },
+        }
+    }
+
+    /// `212 - 330 (mean 251, sd 31, cv 0.12)`
+    pub fn display(&self) -> String {
+        format!(
+            "{} - {} (mean {}, sd {}, cv {:.2})",
+            self.min as i32,
+            self.max as i32,
+            self.mean as i32,
+            self.std_dev as i32,
+            self.coefficient_of_variation
+        )
+    }
+}
+
+impl Scorecard {
+    pub fn new(builder: &StowSlotBuilder, tolerance: f32) -> Self {
+        let regular: Vec<&StowSlot> = builder
+            .stow_slots
+            .iter()
+            .filter(|s| !s.is_floater)
+            .collect();
+        let target_pph = match &builder.algorithm_config {
+            Some(config) if config.algorithm == Algorithm::TargetPPH => config.target_pph as f32,
+            _ => PphSpread::new(&weighted_pph(&regular)).mean,
+        };
+        let outside = |slots: &[&StowSlot]| {
+            slots
+                .iter()
+                .filter(|s| (s.weighted_pph - target_pph).abs() > target_pph * tolerance)
+                .count()
+        };
+
+        let coverage = builder.validate_coverage();
+        let floor = builder.floor();
+        let mut clusters = Vec::new();
+        for cluster in &floor.clusters {
+            let slots: Vec<&StowSlot> = builder
+                .stow_slots
+                .iter()
+                .filter(|s| s.cluster == cluster.cluster)
+                .collect();
+            let cluster_regular: Vec<&StowSlot> =
+                slots.iter().filter(|s| !s.is_floater).cloned().collect();
+            let unassigned_aisles = coverage.missing_in(&cluster.cluster);
+            clusters.push(ClusterScorecard {
+                cluster: cluster.cluster.clone(),
+                slots: slots.len(),
+                floaters: slots.len() - cluster_regular.len(),
+                spread: PphSpread::new(&weighted_pph(&cluster_regular)),
+                outside_tolerance: outside(&cluster_regular),
+                non_consecutive: slots.iter().filter(|s| !s.is_consecutive()).count(),
+                unassigned_aisles,
+            });
+        }
+
+        Self {
+            slots: builder.stow_slots.len(),
+            floaters: builder.stow_slots.len() - regular.len(),
+            target_pph,
+            tolerance,
+            spread: PphSpread::new(&weighted_pph(&regular)),
+            outside_tolerance: outside(&regular),
+            non_consecutive: clusters.iter().map(|c| c.non_consecutive).sum(),
+            unassigned_aisles: coverage.missing_labels(),
+            clusters,
+        }
+    }
+
+    pub fn display(&self) {
+        println!(
+            "Scorecard: {} slots ({} floaters), weighted PPH {}",
+            self.slots,
+            self.floaters,
+            self.spread.display()
+        );
+        println!(
+            "  {} slots outside {}% of {} PPH, {} not consecutive, {} unassigned aisles{}",
+            self.outside_tolerance,
+            (self.tolerance * 100.0).round(),
+            self.target_pph as i32,
+            self.non_consecutive,
+            self.unassigned_aisles.len(),
+            if self.unassigned_aisles.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", self.unassigned_aisles.join(", "))
+            }
+        );
+        for c in &self.clusters {
+            println!(
+                "  cluster {}: {} slots ({} floaters), weighted PPH {}, {} outside tolerance, {} not consecutive, {} unassigned",
+                c.cluster,
+                c.slots,
+                c.floaters,
+                c.spread.display(),
+                c.outside_tolerance,
+                c.non_consecutive,
+                c.unassigned_aisles.len()
+            );
+        }
+    }
+}
+
+fn weighted_pph(slots: &[&StowSlot]) -> Vec<f32> {
+    slots.iter().map(|s| s.weighted_pph).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{builder, floor, slot};
+
+    #[test]
+    fn unassigned_aisles_come_from_the_coverage_check() {
+        let f = floor(&[
+            ("A", &[(1, 10), (2, 10), (3, 10)]),
+            ("B", &[(1, 10), (2, 10)]),
+        ]);
+        let mut b = builder(f, &[("A", &[1, 2]), ("B", &[1])]);
+        // B-2 sits in a slot of cluster A, which still leaves A-3 unassigned.
+        let mut stray = slot(&b, "B", &[2]);
+        stray.cluster = "A".to_string();
+        b.stow_slots.push(stray);
+
+        let scorecard = Scorecard::new(&b, 0.1);
+        assert_eq!(scorecard.unassigned_aisles, ["A-3"]);
+        let unassigned: Vec<&[String]> = scorecard
+            .clusters
+            .iter()
+            .map(|c| c.unassigned_aisles.as_slice())
+            .collect();
+        assert_eq!(unassigned, [&["A-3".to_string()][..], &[]]);
+    }
+
+    #[test]
+    fn unassigned_aisles_stay_with_a_hyphenated_cluster() {
+        let f = floor(&[
+            ("DD-A", &[(1, 10), (2, 10), (3, 10), (4, 10)]),
+            ("DD", &[(1, 10), (2, 10)]),
+        ]);
+        let b = builder(f, &[("DD-A", &[1, 2]), ("DD", &[1, 2])]);
+
+        let scorecard = Scorecard::new(&b, 0.1);
+        assert_eq!(scorecard.unassigned_aisles, ["DD-A-3", "DD-A-4"]);
+        let unassigned: Vec<(&str, usize)> = scorecard
+            .clusters
+            .iter()
+            .map(|c| (c.cluster.as_str(), c.unassigned_aisles.len()))
+            .collect();
+        assert_eq!(unassigned, [("DD-A", 2), ("DD", 0)]);
+    }
+}
@@ -0,0 +1,83 @@
+//! Small hand-built floors for unit tests.
+
+use crate::layout::FloorLayout;
+use crate::models::{BagRecord, Floor, PlanningContext, SortZone, StowSlot, StowSlotBuilder};
+
+/// A one-hour shift, so a slot's PPH is its package count.
+pub(crate) fn context() -> PlanningContext {
+    PlanningContext {
+        total_hours: 1.0,
+        ..Default::default()
+    }
+}
+
+/// One bag record for sort zone `cluster-aisle.1A`.
+pub(crate) fn bag(cluster: &str, aisle: u32, packages: i32) -> BagRecord {
+    BagRecord {
+        sort_zone: SortZone {
+            cluster: cluster.to_string(),
+            aisle,
+            level: 1,
+            column: "A".to_string(),
+            label: format!("{}-{}.1A", cluster, aisle),
+        },
+        planned_bag_count: 0,
+        planned_package_count: packages,
+        source: None,
+        difficulty: 1.0,
+        bag_cost: 0.0,
+    }
+}
+
+/// A floor with one bag record per aisle, given as `(cluster, [(aisle, packages)])`, over
+/// a one-hour shift.
+pub(crate) fn floor(clusters: &[(&str, &[(u32, i32)])]) -> Floor {
+    floor_with_layout(clusters, "")
+}
+
+/// Like `floor`, laid out by the station layout `layout` given as TOML.
+pub(crate) fn floor_with_layout(clusters: &[(&str, &[(u32, i32)])], layout: &str) -> Floor {
+    let bags = clusters
+        .iter()
+        .flat_map(|(cluster, aisles)| {
+            aisles
+                .iter()
+                .map(move |&(aisle, packages)| bag(cluster, aisle, packages))
+        })
+        .collect();
+    let context = PlanningContext {
+        layout: FloorLayout::from_toml(layout).expect("valid layout"),
+        ..context()
+    };
+    Floor::new(bags, context)
+}
+
+/// A builder over `floor` whose plan is `slots`, each given as `(cluster, [aisles])`.
+pub(crate) fn builder(floor: Floor, slots: &[(&str, &[u32])]) -> StowSlotBuilder {
+    let mut builder = floor.create_stow_slot_builder();
+    for (cluster, aisles) in slots {
+        let slot = slot(&builder, cluster, aisles);
+        builder.stow_slots.push(slot);
+    }
+    builder
+}
+
+/// A slot of the floor's own aisles, which have to exist.
+pub(crate) fn slot(builder: &StowSlotBuilder, cluster: &str, aisles: &[u32]) -> StowSlot {
+    let floor = builder.floor();
+    let found = floor.get_cluster(cluster).expect("cluster on the floor");
+    let aisles = aisles
+        .iter()
+        .map(|&n| found.get_aisle(n).expect("aisle on the floor").clone())
+        .collect();
+    StowSlot::new(cluster.to_string(), aisles, floor.context.total_hours)
+}
+
+/// Aisle numbers of every slot, in plan order.
+pub(crate) fn plan(builder: &StowSlotBuilder) -> Vec<Vec<u32>> {
+    builder
+        .stow_slots
+        .iter()
+        .map(|s| s.aisles.iter().map(|a| a.aisle_num).collect())
+        .collect()
+}
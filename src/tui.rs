@@ -0,0 +1,322 @@
+//! Interactive editor for a finished plan: every cluster is drawn as a strip of aisles cut
+//! into slots, and the selected slot can be resized, split, merged, removed or locked
+//! before the plan is exported.
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph, Row, Table, TableState, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::error::Error;
+use std::path::Path;
+use AMZL_Staffing::models::{StowSlot, StowSlotBuilder};
+
+const HELP: &str = "←/→ slot  ↑/↓ cluster  [ ] move right edge  s split  m merge with next  x remove  l lock  u undo  U redo  r re-plan  e export  q done";
+
+struct Editor<'a> {
+    builder: &'a mut StowSlotBuilder,
+    output: &'a Path,
+    selected: usize,
+    status: String,
+}
+
+/// Runs the editor until the user is done. The builder keeps every edit.
+pub fn run(builder: &mut StowSlotBuilder, output: &Path) -> Result<(), Box<dyn Error>> {
+    let mut terminal = ratatui::init();
+    let mut editor = Editor {
+        builder,
+        output,
+        selected: 0,
+        status: String::new(),
+    };
+    let result = editor.event_loop(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl Editor<'_> {
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> Result<(), Box<dyn Error>> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            // Windows also reports key releases.
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let result = match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Left => self.select(self.selected.saturating_sub(1)),
+                KeyCode::Right => self.select(self.selected + 1),
+                KeyCode::Up => self.select_cluster(-1),
+                KeyCode::Down => self.select_cluster(1),
+                KeyCode::Char('[') => self
+                    .builder
+                    .move_boundary(self.selected, -1)
+                    .map_err(|e| e.to_string()),
+                KeyCode::Char(']') => self
+                    .builder
+                    .move_boundary(self.selected, 1)
+                    .map_err(|e| e.to_string()),
+                KeyCode::Char('s') => self.split(),
+                KeyCode::Char('m') => self
+                    .builder
+                    .merge_slots(self.selected)
+                    .map_err(|e| e.to_string()),
+                KeyCode::Char('x') | KeyCode::Delete => self
+                    .builder
+                    .remove_slot(self.selected)
+                    .map_err(|e| e.to_string()),
+                KeyCode::Char('l') => self.builder.toggle_slot_lock(self.selected),
+                KeyCode::Char('u') => self.undo(),
+                KeyCode::Char('U') => self.redo(),
+                KeyCode::Char('r') => self.replan(),
+                KeyCode::Char('e') => self.export(),
+                _ => Ok(()),
+            };
+            if let Err(e) = result {
+                self.status = e;
+            }
+            self.selected = self
+                .selected
+                .min(self.builder.stow_slots.len().saturating_sub(1));
+        }
+    }
+
+    fn select(&mut self, index: usize) -> Result<(), String> {
+        if index < self.builder.stow_slots.len() {
+            self.selected = index;
+            self.status.clear();
+        }
+        Ok(())
+    }
+
+    /// Jumps to the first slot of the previous or next cluster.
+    fn select_cluster(&mut self, step: i32) -> Result<(), String> {
+        let clusters = self.clusters();
+        let Some(current) = self
+            .builder
+            .stow_slots
+            .get(self.selected)
+            .and_then(|s| clusters.iter().position(|c| *c == s.cluster))
+        else {
+            return Ok(());
+        };
+        let Some(cluster) = current
+            .checked_add_signed(step as isize)
+            .and_then(|i| clusters.get(i))
+        else {
+            return Ok(());
+        };
+        match self
+            .builder
+            .stow_slots
+            .iter()
+            .position(|s| &s.cluster == cluster)
+        {
+            Some(index) => self.select(index),
+            None => Ok(()),
+        }
+    }
+
+    fn split(&mut self) -> Result<(), String> {
+        let half = self
+            .builder
+            .stow_slots
+            .get(self.selected)
+            .map_or(0, |s| s.aisles.len() / 2);
+        self.builder
+            .split_slot(self.selected, half)
+            .map_err(|e| e.to_string())
+    }
+
+    fn undo(&mut self) -> Result<(), String> {
+        let change = self.builder.undo().ok_or("nothing to undo")?;
+        self.status = format!("Undid: {}", change);
+        Ok(())
+    }
+
+    fn redo(&mut self) -> Result<(), String> {
+        let change = self.builder.redo().ok_or("nothing to redo")?;
+        self.status = format!("Redid: {}", change);
+        Ok(())
+    }
+
+    /// Plans everything that is not locked again with the same settings.
+    fn replan(&mut self) -> Result<(), String> {
+        let config = self
+            .builder
+            .algorithm_config
+            .clone()
+            .ok_or("the plan has no algorithm settings to re-run")?;
+        self.builder.start_algorithm(config)?;
+        self.status = "Re-planned around locked slots".to_string();
+        Ok(())
+    }
+
+    fn export(&mut self) -> Result<(), String> {
+        self.builder
+            .export_csv(self.output)
+            .map_err(|e| format!("{}: {}", self.output.display(), e))?;
+        self.status = format!("Stow slots written to {}", self.output.display());
+        Ok(())
+    }
+
+    fn clusters(&self) -> Vec<String> {
+        let mut clusters: Vec<String> = Vec::new();
+        for slot in &self.builder.stow_slots {
+            if !clusters.contains(&slot.cluster) {
+                clusters.push(slot.cluster.clone());
+            }
+        }
+        clusters
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let lines: Vec<Line> = self
+            .clusters()
+            .iter()
+            .map(|cluster| self.strip(cluster))
+            .collect();
+        let [strips, table, footer] = Layout::vertical([
+            Constraint::Max(lines.len() as u16 + 2),
+            Constraint::Min(5),
+            Constraint::Length(4),
+        ])
+        .areas(frame.area());
+
+        frame.render_widget(
+            Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+                Block::bordered().title(format!(" {} stow slots ", self.builder.stow_slots.len())),
+            ),
+            strips,
+        );
+
+        self.draw_table(frame, table);
+
+        let status = if self.status.is_empty() {
+            self.selected_slot()
+                .map(|s| s.display_range())
+                .unwrap_or_default()
+        } else {
+            self.status.clone()
+        };
+        frame.render_widget(
+            Paragraph::new(vec![Line::from(status), Line::from(HELP).dim()])
+                .block(Block::bordered()),
+            footer,
+        );
+    }
+
+    /// `A │7 8 9 10│11 12│…`, with the selected slot highlighted.
+    fn strip(&self, cluster: &str) -> Line<'static> {
+        let mut spans = vec![Span::styled(
+            format!("{:>3} ", cluster),
+            Style::new().add_modifier(Modifier::BOLD),
+        )];
+        for (i, slot) in self
+            .builder
+            .stow_slots
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.cluster == cluster)
+        {
+            spans.push(Span::raw("│"));
+            let aisles: Vec<String> = slot
+                .floor_aisles()
+                .map(|a| a.aisle_num.to_string())
+                .collect();
+            spans.push(Span::styled(
+                aisles.join(" "),
+                slot_style(slot, i == self.selected),
+            ));
+        }
+        spans.push(Span::raw("│"));
+        Line::from(spans)
+    }
+
+    fn draw_table(&self, frame: &mut Frame, area: ratatui::layout::Rect) {
+        let Some(cluster) = self.selected_slot().map(|s| s.cluster.clone()) else {
+            return;
+        };
+        let slots: Vec<(usize, &StowSlot)> = self
+            .builder
+            .stow_slots
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.cluster == cluster)
+            .collect();
+        let max_pph = slots
+            .iter()
+            .map(|(_, s)| s.weighted_pph)
+            .fold(1.0_f32, f32::max);
+        let rows = slots.iter().map(|(i, slot)| {
+            let bar = "█".repeat((slot.weighted_pph / max_pph * 20.0).round() as usize);
+            let mut flags = Vec::new();
+            if slot.locked {
+                flags.push("locked");
+            }
+            if slot.is_floater {
+                flags.push("floater");
+            }
+            Row::new(vec![
+                (i + 1).to_string(),
+                slot.display_range(),
+                (slot.pph as i32).to_string(),
+                (slot.weighted_pph as i32).to_string(),
+                bar,
+                slot.total_packages().to_string(),
+                slot.total_bags().to_string(),
+                flags.join(", "),
+                slot.stower.clone().unwrap_or_default(),
+            ])
+            .style(slot_style(slot, false))
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(4),
+                Constraint::Length(14),
+                Constraint::Length(5),
+                Constraint::Length(8),
+                Constraint::Length(20),
+                Constraint::Length(8),
+                Constraint::Length(5),
+                Constraint::Length(15),
+                Constraint::Min(6),
+            ],
+        )
+        .header(
+            Row::new(vec![
+                "#", "Aisles", "PPH", "Weighted", "", "Packages", "Bags", "", "Stower",
+            ])
+            .add_modifier(Modifier::BOLD),
+        )
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(Block::bordered().title(format!(" Cluster {} ", cluster)));
+        let mut state = TableState::default()
+            .with_selected(slots.iter().position(|(i, _)| *i == self.selected));
+        frame.render_stateful_widget(table, area, &mut state);
+    }
+
+    fn selected_slot(&self) -> Option<&StowSlot> {
+        self.builder.stow_slots.get(self.selected)
+    }
+}
+
+fn slot_style(slot: &StowSlot, selected: bool) -> Style {
+    let style = if slot.locked {
+        Style::new().fg(Color::Yellow)
+    } else if slot.is_floater {
+        Style::new().fg(Color::Cyan)
+    } else {
+        Style::new()
+    };
+    if selected {
+        style.add_modifier(Modifier::REVERSED)
+    } else {
+        style
+    }
+}
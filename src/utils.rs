@@ -1,13 +1,22 @@
-use crate::models::{Algorithm, BagRecord};
-use csv::Reader;
+use crate::layout::FloorLayout;
+use crate::models::{
+    Algorithm, AlgorithmConfig, Assignment, BagRecord, GapPolicy, PlanningContext, SortZoneSchema,
+    WorkloadWeights,
+};
+use calamine::{open_workbook, Reader, Xlsx};
+use csv::{ReaderBuilder, StringRecord};
 use serde::{Deserialize, Serialize};
 use std::{
     error::Error,
+    fmt,
     fs::{self, File},
+    io::{self, Read},
     path::{Path, PathBuf},
+    str::FromStr,
 };
 
 #[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
 pub struct Config {
     pub target_pph: i32,
     pub total_hours: f32,
@@ -15,6 +24,20 @@ pub struct Config {
     pub algorithm: Algorithm,
     pub max_aisle_count: i32,
     pub min_aisle_count: i32,
+    pub floater_pph: f32,
+    pub floaters_per_cluster: i32,
+    /// How far, as a fraction, a slot may stray from the target before the scorecard flags it.
+    pub tolerance: f32,
+    /// Bridge, Break or ZeroVolume: how slots treat missing aisle numbers.
+    pub gap_policy: GapPolicy,
+    /// Regex with named captures `cluster`, `aisle`, `level` and `column`.
+    pub sort_zone_pattern: String,
+    /// Station layout file used to estimate walking between aisles.
+    pub layout: Option<PathBuf>,
+    /// Header aliases for the input columns.
+    pub columns: ColumnMapping,
+    /// Seconds per package and per bag, and difficulty weights per level and column.
+    pub workload: WorkloadWeights,
     // Add other configuration fields as needed
 }
 
@@ -26,6 +49,46 @@ impl Config {
         Self::read_or_create(&path)
     }
 
+    pub fn planning_context(&self) -> Result<PlanningContext, Box<dyn Error>> {
+        let layout = match &self.layout {
+            Some(path) => {
+                FloorLayout::load(path).map_err(|e| format!("layout {}: {}", path.display(), e))?
+            }
+            None => FloorLayout::default(),
+        };
+        Ok(PlanningContext {
+            total_hours: self.total_hours,
+            workload: self.workload.clone(),
+            layout,
+        })
+    }
+
+    pub fn algorithm_config(&self) -> AlgorithmConfig {
+        AlgorithmConfig {
+            algorithm: self.algorithm,
+            target_pph: self.target_pph,
+            target_hc: self.target_hc,
+            max_aisle_count: self.max_aisle_count,
+            min_aisle_count: self.min_aisle_count,
+            floater_pph: self.floater_pph,
+            floaters_per_cluster: self.floaters_per_cluster,
+            gap_policy: self.gap_policy,
+        }
+    }
+
+    pub fn sort_zone_schema(&self) -> Result<SortZoneSchema, String> {
+        SortZoneSchema::new(&self.sort_zone_pattern)
+            .map_err(|e| format!("invalid sort_zone_pattern: {}", e))
+    }
+
+    pub fn input_options(&self) -> Result<InputOptions, String> {
+        Ok(InputOptions {
+            schema: self.sort_zone_schema()?,
+            columns: self.columns.clone(),
+            sheet: SheetSelector::default(),
+        })
+    }
+
     fn read_or_create(path: &Path) -> Result<Self, Box<dyn Error>> {
         if !path.exists() {
             // Create parent directories if they don't exist
@@ -44,7 +107,7 @@ impl Config {
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
         }
-        println!("{}", path.to_str().unwrap());
+        eprintln!("{}", path.display());
         fs::write(path, toml)?;
         Ok(())
     }
@@ -71,17 +134,727 @@ impl Default for Config {
             algorithm: Algorithm::TargetPPH,
             max_aisle_count: 3,
             min_aisle_count: 2,
+            floater_pph: 150.0,
+            floaters_per_cluster: 0,
+            tolerance: 0.1,
+            gap_policy: GapPolicy::default(),
+            sort_zone_pattern: SortZoneSchema::DEFAULT_PATTERN.to_string(),
+            layout: None,
+            columns: ColumnMapping::default(),
+            workload: WorkloadWeights::default(),
+        }
+    }
+}
+
+/// A row that was skipped while reading a CSV, with its line number in the file.
+#[derive(Debug, Clone)]
+pub struct RejectedRow {
+    /// The file the row came from, set when several files are read together.
+    pub file: Option<String>,
+    pub line: u64,
+    pub reason: String,
+    pub fields: Vec<String>,
+}
+
+impl RejectedRow {
+    /// `line 12`, or `wave2.csv:12` when the file is known.
+    pub fn location(&self) -> String {
+        match &self.file {
+            Some(file) => format!("{}:{}", file, self.line),
+            None => format!("line {}", self.line),
+        }
+    }
+}
+
+/// The rows of a CSV that could be read, and the ones that could not.
+#[derive(Debug, Default)]
+pub struct CsvReport {
+    /// The header row of the input, or of the first input when several are read together.
+    pub headers: Vec<String>,
+    pub records: Vec<BagRecord>,
+    pub rejected: Vec<RejectedRow>,
+}
+
+impl CsvReport {
+    pub fn display_rejected(&self) {
+        if self.rejected.is_empty() {
+            return;
+        }
+        eprintln!(
+            "Ignored {} of {} rows:",
+            self.rejected.len(),
+            self.rejected.len() + self.records.len()
+        );
+        for row in &self.rejected {
+            eprintln!("  {}: {}", row.location(), row.reason);
+        }
+    }
+
+    /// The records, or an error listing every rejected row if there were any.
+    pub fn into_records(self) -> Result<Vec<BagRecord>, Box<dyn Error>> {
+        if self.rejected.is_empty() {
+            return Ok(self.records);
+        }
+        let rows: Vec<String> = self
+            .rejected
+            .iter()
+            .map(|row| format!("{}: {}", row.location(), row.reason))
+            .collect();
+        Err(format!("{} invalid rows\n{}", rows.len(), rows.join("\n")).into())
+    }
+
+    /// Writes the rejected rows, prefixed with their line number and reason, to a CSV under
+    /// the input's own header row.
+    pub fn write_quarantine<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut writer = csv::WriterBuilder::new().flexible(true).from_path(path)?;
+        let mut header = vec!["Line".to_string(), "Reason".to_string()];
+        header.extend(self.headers.iter().cloned());
+        writer.write_record(&header)?;
+        for row in &self.rejected {
+            let line = match &row.file {
+                Some(_) => row.location(),
+                None => row.line.to_string(),
+            };
+            let mut fields = vec![line, row.reason.clone()];
+            fields.extend(row.fields.iter().cloned());
+            writer.write_record(&fields)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Header names accepted for each required input column.
+///
+/// Headers are compared ignoring case, spaces and punctuation. When no alias matches,
+/// a header holding an alias as whole words is used, and failing that the most similar
+/// remaining header if it is close enough.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ColumnMapping {
+    pub sort_zone: Vec<String>,
+    pub bag_count: Vec<String>,
+    pub package_count: Vec<String>,
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        let aliases = |names: &[&str]| names.iter().map(|n| n.to_string()).collect();
+        ColumnMapping {
+            sort_zone: aliases(&["Sort Zone", "Zone", "Sort Location", "Stow Location"]),
+            bag_count: aliases(&["Planned Bag Count", "Bag Count", "Bags", "Planned Bags"]),
+            package_count: aliases(&[
+                "Planned Package Count",
+                "Package Count",
+                "Packages",
+                "Planned Packages",
+                "Pkg Count",
+                "Pkgs",
+            ]),
+        }
+    }
+}
+
+/// Positions of the required columns in a header row.
+#[derive(Debug, Clone, Copy)]
+struct ColumnIndices {
+    sort_zone: usize,
+    bag_count: usize,
+    package_count: usize,
+}
+
+impl ColumnMapping {
+    /// How similar a header must be to an alias to be picked up automatically.
+    const MIN_SIMILARITY: f64 = 0.8;
+
+    fn resolve(&self, headers: &[String]) -> Result<ColumnIndices, String> {
+        let normalized: Vec<String> = headers.iter().map(|h| normalize_header(h)).collect();
+        let columns = [
+            ("Sort Zone", &self.sort_zone),
+            ("Planned Bag Count", &self.bag_count),
+            ("Planned Package Count", &self.package_count),
+        ];
+        let mut found: [Option<usize>; 3] = [None; 3];
+
+        // Exact alias matches win, so similar names like "Bag Count" and "Package Count"
+        // can't steal each other's header.
+        for (i, (_, aliases)) in columns.iter().enumerate() {
+            found[i] = aliases.iter().find_map(|alias| {
+                let alias = normalize_header(alias);
+                normalized.iter().position(|h| *h == alias)
+            });
+        }
+        // Then headers that hold a whole alias as separate words, like "Zone Code" or
+        // "Package Count (Wave 1)", in the order the aliases are listed.
+        let words: Vec<Vec<String>> = headers.iter().map(|h| header_words(h)).collect();
+        for (i, (_, aliases)) in columns.iter().enumerate() {
+            if found[i].is_some() {
+                continue;
+            }
+            found[i] = aliases.iter().find_map(|alias| {
+                let alias = header_words(alias);
+                (0..headers.len())
+                    .filter(|h| !found.contains(&Some(*h)))
+                    .find(|&h| contains_words(&words[h], &alias))
+            });
+        }
+        // Last, the most similar remaining header, if it is close enough.
+        for (i, (_, aliases)) in columns.iter().enumerate() {
+            if found[i].is_some() {
+                continue;
+            }
+            found[i] = normalized
+                .iter()
+                .enumerate()
+                .filter(|(h, _)| !found.contains(&Some(*h)))
+                .map(|(h, header)| {
+                    let score = aliases
+                        .iter()
+                        .map(|alias| {
+                            strsim::normalized_levenshtein(header, &normalize_header(alias))
+                        })
+                        .fold(0.0, f64::max);
+                    (h, score)
+                })
+                .filter(|(_, score)| *score >= Self::MIN_SIMILARITY)
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(h, _)| h);
+        }
+
+        let missing: Vec<String> = columns
+            .iter()
+            .zip(found)
+            .filter(|(_, index)| index.is_none())
+            .map(|((name, aliases), _)| format!("'{}' (aliases: {})", name, aliases.join(", ")))
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!(
+                "missing required column {}; available headers: {}",
+                missing.join(", "),
+                headers.join(", ")
+            ));
+        }
+        Ok(ColumnIndices {
+            sort_zone: found[0].unwrap_or_default(),
+            bag_count: found[1].unwrap_or_default(),
+            package_count: found[2].unwrap_or_default(),
+        })
+    }
+}
+
+/// Whether `alias` appears in `header` as a run of whole words.
+fn contains_words(header: &[String], alias: &[String]) -> bool {
+    !alias.is_empty() && header.windows(alias.len()).any(|window| window == alias)
+}
+
+/// The lowercase words of a header, split at anything that is not a letter or digit.
+fn header_words(header: &str) -> Vec<String> {
+    header
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn normalize_header(header: &str) -> String {
+    header
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Which worksheet of a workbook to read.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum SheetSelector {
+    #[default]
+    First,
+    Name(String),
+    /// Counted from 1, like the tabs along the bottom of Excel.
+    Index(usize),
+}
+
+impl FromStr for SheetSelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<usize>() {
+            Ok(0) => Err("sheet numbers start at 1".to_string()),
+            Ok(index) => Ok(SheetSelector::Index(index)),
+            Err(_) => Ok(SheetSelector::Name(s.to_string())),
         }
     }
 }
 
-pub fn read_csv(file_path: &str) -> Result<Vec<BagRecord>, Box<dyn Error>> {
-    let mut records: Vec<BagRecord> = Vec::new();
+impl fmt::Display for SheetSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SheetSelector::First => write!(f, "first sheet"),
+            SheetSelector::Name(name) => write!(f, "sheet '{}'", name),
+            SheetSelector::Index(index) => write!(f, "sheet {}", index),
+        }
+    }
+}
+
+/// Everything needed to turn a planning export into `BagRecord`s.
+#[derive(Debug, Clone, Default)]
+pub struct InputOptions {
+    pub schema: SortZoneSchema,
+    pub columns: ColumnMapping,
+    pub sheet: SheetSelector,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputKind {
+    Csv,
+    Xlsx,
+}
+
+impl InputKind {
+    /// Goes by extension, then by the zip signature every .xlsx starts with, so a
+    /// renamed or extensionless workbook dropped on the exe still opens.
+    pub fn detect<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("xlsx" | "xlsm") => return Ok(InputKind::Xlsx),
+            Some("csv") => return Ok(InputKind::Csv),
+            _ => {}
+        }
+        let mut magic = [0u8; 4];
+        let mut file = File::open(path)?;
+        let read = file.read(&mut magic)?;
+        if read == magic.len() && magic == *b"PK\x03\x04" {
+            Ok(InputKind::Xlsx)
+        } else {
+            Ok(InputKind::Csv)
+        }
+    }
+}
+
+/// Turns a header row and numbered data rows into `BagRecord`s, keeping the bad rows aside.
+pub fn read_rows<I>(
+    headers: &[String],
+    rows: I,
+    options: &InputOptions,
+) -> Result<CsvReport, Box<dyn Error>>
+where
+    I: IntoIterator<Item = (u64, Vec<String>)>,
+{
+    let columns = options.columns.resolve(headers)?;
+    let mut report = CsvReport {
+        headers: headers.to_vec(),
+        ..Default::default()
+    };
+    for (line, fields) in rows {
+        if fields.iter().all(|field| field.trim().is_empty()) {
+            report.rejected.push(RejectedRow {
+                file: None,
+                line,
+                reason: "blank row".to_string(),
+                fields,
+            });
+            continue;
+        }
+        let field = |index: usize| -> Result<&str, String> {
+            fields
+                .get(index)
+                .map(|f| f.trim())
+                .ok_or_else(|| format!("{}: missing", headers[index]))
+        };
+        let number = |index: usize| -> Result<i32, String> {
+            let value = field(index)?;
+            value
+                .parse::<i32>()
+                .map_err(|e| format!("{}: '{}' {}", headers[index], value, e))
+        };
+        let record = (|| {
+            Ok::<_, String>(BagRecord {
+                sort_zone: options.schema.parse(field(columns.sort_zone)?)?,
+                planned_bag_count: number(columns.bag_count)?,
+                planned_package_count: number(columns.package_count)?,
+                source: None,
+                difficulty: 1.0,
+                bag_cost: 0.0,
+            })
+        })();
+        match record {
+            Ok(record) => report.records.push(record),
+            Err(reason) => report.rejected.push(RejectedRow {
+                file: None,
+                line,
+                reason,
+                fields,
+            }),
+        }
+    }
+    Ok(report)
+}
+
+/// Reads every row it can, collecting the bad ones instead of stopping at the first.
+pub fn read_csv_lenient(
+    file_path: &str,
+    options: &InputOptions,
+) -> Result<CsvReport, Box<dyn Error>> {
     let file = File::open(file_path)?;
-    let mut rdr = Reader::from_reader(file);
-    for result in rdr.deserialize() {
-        let record: BagRecord = result?;
-        records.push(record);
+    let mut rdr = ReaderBuilder::new().flexible(true).from_reader(file);
+    let headers: Vec<String> = rdr.headers()?.iter().map(str::to_string).collect();
+    let mut rows = Vec::new();
+    // Rows the csv reader cannot turn into text, kept aside like any other bad row.
+    let mut unreadable = Vec::new();
+    for result in rdr.byte_records() {
+        let row = result?;
+        let line = row.position().map(|p| p.line()).unwrap_or(0);
+        match StringRecord::from_byte_record(row) {
+            Ok(row) => rows.push((line, row.iter().map(str::to_string).collect())),
+            Err(e) => unreadable.push(RejectedRow {
+                file: None,
+                line,
+                reason: format!("not UTF-8 text: {}", e),
+                fields: e
+                    .into_byte_record()
+                    .iter()
+                    .map(|field| String::from_utf8_lossy(field).into_owned())
+                    .collect(),
+            }),
+        }
+    }
+    let mut report = read_rows(&headers, rows, options)?;
+    report.rejected.extend(unreadable);
+    report.rejected.sort_by_key(|row| row.line);
+    Ok(report)
+}
+
+/// Reads every row it can from a worksheet of an .xlsx workbook.
+pub fn read_xlsx_lenient(
+    file_path: &str,
+    options: &InputOptions,
+) -> Result<CsvReport, Box<dyn Error>> {
+    let mut workbook: Xlsx<_> = open_workbook(file_path)?;
+    let sheets = workbook.sheet_names();
+    let sheet = match &options.sheet {
+        SheetSelector::First => sheets.first(),
+        SheetSelector::Name(name) => sheets.iter().find(|s| s.eq_ignore_ascii_case(name)),
+        SheetSelector::Index(index) => index.checked_sub(1).and_then(|i| sheets.get(i)),
+    }
+    .ok_or_else(|| {
+        format!(
+            "no {}; the workbook has: {}",
+            options.sheet,
+            sheets.join(", ")
+        )
+    })?
+    .clone();
+
+    let range = workbook.worksheet_range(&sheet)?;
+    let first_row = range.start().map(|(row, _)| row as u64).unwrap_or(0);
+    let mut rows = range.rows().enumerate().map(|(i, cells)| {
+        let line = first_row + i as u64 + 1;
+        (
+            line,
+            cells.iter().map(|c| c.to_string()).collect::<Vec<_>>(),
+        )
+    });
+    let headers = rows
+        .next()
+        .map(|(_, headers)| headers)
+        .ok_or_else(|| format!("sheet '{}' is empty (pick another with --sheet)", sheet))?;
+    read_rows(&headers, rows, options)
+}
+
+/// Reads a CSV or .xlsx export, whichever `path` turns out to be.
+pub fn read_input_lenient(
+    file_path: &str,
+    options: &InputOptions,
+) -> Result<CsvReport, Box<dyn Error>> {
+    match InputKind::detect(file_path)? {
+        InputKind::Csv => read_csv_lenient(file_path, options),
+        InputKind::Xlsx => read_xlsx_lenient(file_path, options),
+    }
+}
+
+/// Like [`read_input_lenient`], but fails if any row cannot be read.
+pub fn read_input(
+    file_path: &str,
+    options: &InputOptions,
+) -> Result<Vec<BagRecord>, Box<dyn Error>> {
+    read_input_lenient(file_path, options)?.into_records()
+}
+
+/// Reads several exports into one report. Rejected rows name their file when there is
+/// more than one, and with `by_source` every record is tagged with its file's name (see
+/// [`source_tags`]).
+pub fn read_inputs_lenient<P: AsRef<Path>>(
+    paths: &[P],
+    options: &InputOptions,
+    by_source: bool,
+) -> Result<CsvReport, Box<dyn Error>> {
+    let paths: Vec<&Path> = paths.iter().map(|p| p.as_ref()).collect();
+    let tags = source_tags(&paths);
+    let mut merged = CsvReport::default();
+    for (path, tag) in paths.iter().zip(tags) {
+        let report = read_input_lenient(&path.to_string_lossy(), options)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        // The whole path when another input has the same file name.
+        let file = path.file_name().map(|name| {
+            if paths.iter().filter(|p| p.file_name() == Some(name)).count() > 1 {
+                path.display().to_string()
+            } else {
+                name.to_string_lossy().into_owned()
+            }
+        });
+        if merged.headers.is_empty() {
+            merged.headers = report.headers;
+        }
+        let source = Some(tag);
+        merged
+            .records
+            .extend(report.records.into_iter().map(|mut record| {
+                if by_source {
+                    record.source = source.clone();
+                }
+                record
+            }));
+        merged
+            .rejected
+            .extend(report.rejected.into_iter().map(|mut row| {
+                if paths.len() > 1 {
+                    row.file = file.clone();
+                }
+                row
+            }));
+    }
+    Ok(merged)
+}
+
+/// A tag for every input file that tells its records apart: the file name without its
+/// extension, with the parent directory in front when two files share a name
+/// (`wave1/plan`, `wave2/plan`), and a number behind when even that is not enough.
+pub fn source_tags(paths: &[&Path]) -> Vec<String> {
+    let stem = |path: &Path| {
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    };
+    let with_parent = |path: &Path| match path.parent().and_then(|p| p.file_name()) {
+        Some(parent) => format!("{}/{}", parent.to_string_lossy(), stem(path)),
+        None => stem(path),
+    };
+    let repeated = |tags: &[String], i: usize| tags.iter().filter(|t| **t == tags[i]).count() > 1;
+
+    let stems: Vec<String> = paths.iter().map(|p| stem(p)).collect();
+    let tags: Vec<String> = (0..paths.len())
+        .map(|i| {
+            if repeated(&stems, i) {
+                with_parent(paths[i])
+            } else {
+                stems[i].clone()
+            }
+        })
+        .collect();
+    (0..tags.len())
+        .map(|i| {
+            if repeated(&tags, i) {
+                let occurrence = tags[..=i].iter().filter(|t| **t == tags[i]).count();
+                format!("{} #{}", tags[i], occurrence)
+            } else {
+                tags[i].clone()
+            }
+        })
+        .collect()
+}
+
+/// Like [`read_inputs_lenient`], but fails if any row of any file cannot be read.
+pub fn read_inputs<P: AsRef<Path>>(
+    paths: &[P],
+    options: &InputOptions,
+    by_source: bool,
+) -> Result<Vec<BagRecord>, Box<dyn Error>> {
+    let report = read_inputs_lenient(paths, options, by_source)?;
+    match paths {
+        [path] => report
+            .into_records()
+            .map_err(|e| format!("{}: {}", path.as_ref().display(), e).into()),
+        _ => report.into_records(),
+    }
+}
+
+/// Reads a CSV, failing with every bad row listed if any row cannot be read.
+pub fn read_csv(file_path: &str, options: &InputOptions) -> Result<Vec<BagRecord>, Box<dyn Error>> {
+    read_csv_lenient(file_path, options)?.into_records()
+}
+
+/// Reads pre-assignments from a CSV with a `Stower` and an `Aisles` column, where aisles
+/// are written like `A-7..A-10` or `A-7`.
+pub fn read_assignments<P: AsRef<Path>>(path: P) -> Result<Vec<Assignment>, Box<dyn Error>> {
+    let path = path.as_ref();
+    let mut reader = ReaderBuilder::new().trim(csv::Trim::All).from_path(path)?;
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("{}: missing '{}' column", path.display(), name))
+    };
+    let (stower, aisles) = (column("Stower")?, column("Aisles")?);
+    let mut assignments = Vec::new();
+    for (i, row) in reader.records().enumerate() {
+        let row = row?;
+        let field = |index: usize| row.get(index).unwrap_or_default();
+        let assignment = Assignment::new(field(stower), field(aisles))
+            .map_err(|e| format!("{}:{}: {}", path.display(), i + 2, e))?;
+        assignments.push(assignment);
+    }
+    Ok(assignments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `contents` to a file of its own in the temp directory.
+    fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    /// A header, a good row, a row with a bad count and a row that is not UTF-8.
+    const MIXED_CSV: &[u8] =
+        b"Sort Zone,Planned Bag Count,Planned Package Count\nA-1.1A,1,20\nA-2.1A,x,5\nA-3.1A,1,\xff\n";
+
+    fn resolve(headers: &[&str]) -> Result<(usize, usize, usize), String> {
+        let headers: Vec<String> = headers.iter().map(|h| h.to_string()).collect();
+        let found = ColumnMapping::default().resolve(&headers)?;
+        Ok((found.sort_zone, found.bag_count, found.package_count))
+    }
+
+    #[test]
+    fn resolves_exact_aliases() {
+        assert_eq!(
+            resolve(&["Planned Package Count", "sort_zone", "BAGS"]),
+            Ok((1, 2, 0))
+        );
+    }
+
+    #[test]
+    fn resolves_aliases_written_as_whole_words() {
+        assert_eq!(
+            resolve(&["Zone Code", "Bags (Wave 1)", "Package Count (Wave 1)"]),
+            Ok((0, 1, 2))
+        );
+    }
+
+    #[test]
+    fn exact_aliases_win_over_whole_words() {
+        assert_eq!(
+            resolve(&["Bag Count Total", "Sort Zone", "Bag Count", "Packages"]),
+            Ok((1, 2, 3))
+        );
+    }
+
+    #[test]
+    fn does_not_match_aliases_inside_words() {
+        assert!(resolve(&["Sort Zone", "Bagsize", "Packages"]).is_err());
+        assert!(resolve(&["Sort Zone", "Bags", "Packagesperhour"]).is_err());
+    }
+
+    #[test]
+    fn source_tags_are_unique() {
+        let tags = |paths: &[&str]| {
+            let paths: Vec<&Path> = paths.iter().map(Path::new).collect();
+            source_tags(&paths)
+        };
+        assert_eq!(tags(&["wave1.csv", "data/wave2.xlsx"]), ["wave1", "wave2"]);
+        assert_eq!(
+            tags(&["wave1/plan.csv", "wave2/plan.csv", "extra.csv"]),
+            ["wave1/plan", "wave2/plan", "extra"]
+        );
+        assert_eq!(
+            tags(&["wave1/plan.csv", "wave1/plan.xlsx", "wave2/plan.csv"]),
+            ["wave1/plan #1", "wave1/plan #2", "wave2/plan"]
+        );
+    }
+
+    #[test]
+    fn resolves_close_misspellings() {
+        assert_eq!(
+            resolve(&["Sort Zone", "Planned Bag Count", "Planned Pakage Count"]),
+            Ok((0, 1, 2))
+        );
+    }
+
+    #[test]
+    fn lenient_reading_keeps_bad_rows_with_their_line() {
+        let path = temp_file("lenient.csv", MIXED_CSV);
+        let report = read_csv_lenient(path.to_str().unwrap(), &InputOptions::default()).unwrap();
+        assert_eq!(report.records.len(), 1);
+        let lines: Vec<u64> = report.rejected.iter().map(|row| row.line).collect();
+        assert_eq!(lines, [3, 4]);
+        assert!(report.rejected[1].reason.starts_with("not UTF-8 text"));
+        assert_eq!(report.rejected[1].fields[..2], ["A-3.1A", "1"]);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn strict_reading_fails_on_any_bad_row() {
+        let path = temp_file("strict.csv", MIXED_CSV);
+        let error = read_csv(path.to_str().unwrap(), &InputOptions::default())
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with("2 invalid rows\nline 3: "), "{}", error);
+        assert!(error.contains("\nline 4: not UTF-8 text"), "{}", error);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn quarantine_starts_with_the_input_header() {
+        let input = temp_file("quarantine-in.csv", MIXED_CSV);
+        let output = temp_file("quarantine-out.csv", b"");
+        let report = read_csv_lenient(input.to_str().unwrap(), &InputOptions::default()).unwrap();
+        report.write_quarantine(&output).unwrap();
+        let written = fs::read_to_string(&output).unwrap();
+        let mut lines = written.lines();
+        assert_eq!(
+            lines.next(),
+            Some("Line,Reason,Sort Zone,Planned Bag Count,Planned Package Count")
+        );
+        assert!(lines.next().unwrap().starts_with("3,"));
+        assert!(lines.next().unwrap().starts_with("4,"));
+        fs::remove_file(input).unwrap();
+        fs::remove_file(output).unwrap();
+    }
+
+    fn read_sheet(sheet: SheetSelector) -> Result<CsvReport, String> {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/test.xlsx");
+        let options = InputOptions {
+            sheet,
+            ..Default::default()
+        };
+        read_input_lenient(path, &options).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn reads_the_selected_sheet_of_a_workbook() {
+        for sheet in [SheetSelector::Index(2), SheetSelector::Name("plan".into())] {
+            let report = read_sheet(sheet).unwrap();
+            assert_eq!(report.records.len(), 2);
+            let lines: Vec<u64> = report.rejected.iter().map(|row| row.line).collect();
+            assert_eq!(lines, [4]);
+        }
+    }
+
+    #[test]
+    fn rejects_sheets_the_workbook_does_not_have() {
+        assert_eq!(
+            read_sheet(SheetSelector::Index(0)).unwrap_err(),
+            "no sheet 0; the workbook has: Notes, Plan"
+        );
+        assert_eq!(
+            read_sheet(SheetSelector::Index(3)).unwrap_err(),
+            "no sheet 3; the workbook has: Notes, Plan"
+        );
     }
-    Ok(records)
 }